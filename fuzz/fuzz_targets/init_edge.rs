@@ -0,0 +1,25 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use lin_kernighan::path::Path;
+
+fuzz_target!(|input: (u8, Vec<(u8, u8)>)| {
+    let (size, edges) = input;
+    let size = size as usize;
+    if size < 2 { return; }
+
+    // Arbitrary edges may leave the path broken, but never out of range.
+    let mut path = Path::uninitialized(size);
+    for (v0, v1) in edges.into_iter().map(|(v0, v1)| (v0 as usize, v1 as usize)) {
+        if v0 < size && v1 < size {
+            path.init_edge(v0, v1);
+        }
+    }
+    path.check_hamiltonian_fast();
+
+    // Any permutation builds a Hamiltonian path.
+    let mut order: Vec<_> = (0..size).collect();
+    order.rotate_left(size / 2);
+    let path = Path::try_from_order(&order).unwrap();
+    assert!(path.is_cycle());
+    assert_eq!(path.vertices_visited().count(), size);
+});
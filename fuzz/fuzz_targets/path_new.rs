@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use lin_kernighan::path::{Path, HamiltonianResult};
+
+fuzz_target!(|data: Vec<(u8, u8)>| {
+    let data = data.into_iter().map(|(v0, v1)| (v0 as usize, v1 as usize)).collect::<Vec<_>>();
+    let size = data.len();
+
+    if let Ok(path) = Path::try_new(data) {
+        assert_eq!(path.check_hamiltonian_fast(), HamiltonianResult::Ok);
+        assert_eq!(path.vertices_visited().count(), size);
+    }
+});
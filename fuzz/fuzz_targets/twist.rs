@@ -0,0 +1,30 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use lin_kernighan::path::{Path, HamiltonianResult};
+
+fn edges(path: &Path) -> Vec<(usize, usize)> {
+    let mut edges: Vec<_> = path.edges_visited().map(|(v0, v1)| (v0.min(v1), v0.max(v1))).collect();
+    edges.sort_unstable();
+    edges
+}
+
+fuzz_target!(|input: (Vec<u8>, Vec<(u8, u8, u8, u8)>)| {
+    let (order, moves) = input;
+    let order: Vec<_> = order.into_iter().map(|v| v as usize).collect();
+
+    let mut path = match Path::try_from_order(&order) {
+        Ok(path) => path,
+        Err(_) => return,
+    };
+
+    for (a0, a1, b0, b1) in moves {
+        let (a, b) = ((a0 as usize, a1 as usize), (b0 as usize, b1 as usize));
+
+        // Invalid moves must leave the path untouched.
+        let before = edges(&path);
+        if path.try_twist(a, b).is_err() {
+            assert_eq!(edges(&path), before);
+        }
+        assert_eq!(path.check_hamiltonian_fast(), HamiltonianResult::Ok);
+    }
+});
@@ -0,0 +1,230 @@
+use rayon::prelude::*;
+use crate::matrix::SymmetricMatrix;
+use crate::path::Path;
+use crate::random::{mix, Random};
+use crate::route::Route;
+use crate::weight::Weight;
+use crate::local_search;
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct AcoConfig {
+    pub ants: usize,
+    /// Exponent of the pheromone when choosing the next vertex.
+    pub alpha: f64,
+    /// Exponent of the inverse of the weight when choosing the next vertex.
+    pub beta: f64,
+    /// Fraction of the pheromone that evaporates at each iteration.
+    pub evaporation: f64,
+    /// Probability of an ant building the best route once the pheromone converged, which sets the lower bound.
+    pub p_best: f64,
+    /// Size of the candidate list of each vertex, the only edges with pheromone.
+    pub candidates: usize,
+    /// Bring the route of each ant to a local minimum before updating the pheromone.
+    pub local_search: bool,
+    pub seed: u64,
+}
+
+impl Default for AcoConfig {
+    fn default() -> Self {
+        Self { ants: 20, alpha: 1.0, beta: 2.0, evaporation: 0.02, p_best: 0.05, candidates: 10, local_search: true, seed: 0 }
+    }
+}
+
+/// Pheromone of the edges to the candidates of each vertex, kept within the MAX–MIN bounds.
+struct Pheromone {
+    candidates: Vec<Vec<usize>>,
+    trail: Vec<Vec<f64>>,
+    min: f64,
+    max: f64,
+}
+
+impl Pheromone {
+    /// Every edge starts at the upper bound for the cost of the initial route.
+    fn new(candidates: Vec<Vec<usize>>, config: &AcoConfig, initial: f64) -> Self {
+        let mut pheromone = Self { candidates, trail: vec![], min: 0.0, max: 0.0 };
+        pheromone.bound(config, initial);
+        pheromone.trail = pheromone.candidates.iter().map(|c| vec![pheromone.max; c.len()]).collect();
+        pheromone
+    }
+
+    /// Bounds for the cost of the best route found so far.
+    fn bound(&mut self, config: &AcoConfig, best: f64) {
+        let size = self.candidates.len() as f64;
+        let root = config.p_best.powf(1.0 / size);
+
+        self.max = 1.0 / (config.evaporation * best);
+        self.min = (self.max * (1.0 - root) / ((size / 2.0 - 1.0) * root)).min(self.max);
+    }
+
+    fn evaporate(&mut self, evaporation: f64) {
+        for trail in self.trail.iter_mut().flat_map(|t| t.iter_mut()) {
+            *trail *= 1.0 - evaporation;
+        }
+    }
+
+    fn deposit(&mut self, (v0, v1): (usize, usize), amount: f64) {
+        for &(x, y) in [(v0, v1), (v1, v0)].iter() {
+            if let Some(k) = self.candidates[x].iter().position(|&u| u == y) {
+                self.trail[x][k] += amount;
+            }
+        }
+    }
+
+    fn clamp(&mut self) {
+        let (min, max) = (self.min, self.max);
+        for trail in self.trail.iter_mut().flat_map(|t| t.iter_mut()) {
+            *trail = trail.clamp(min, max);
+        }
+    }
+
+    /// Evaporates, then deposits the inverse of the cost of the route on its edges, within the bounds.
+    fn update<W: Weight>(&mut self, config: &AcoConfig, route: &Route<W>) {
+        self.evaporate(config.evaporation);
+        for edge in route.path.edges_visited() {
+            self.deposit(edge, 1.0 / route.cost.to_f64());
+        }
+        self.clamp();
+    }
+}
+
+/// Route of an ant from a random vertex. The next vertex is drawn among the candidates not visited yet,
+/// in proportion to their pheromone and closeness. When all were visited, the ant goes to the nearest vertex left.
+fn construct<W: Weight>(tsp: &SymmetricMatrix<W>, pheromone: &Pheromone, config: &AcoConfig, random: &mut Random) -> Route<W> {
+    let size = tsp.size();
+    let mut visited = vec![false; size];
+    let mut path = Path::uninitialized(size);
+
+    let start = random.below(size);
+    let mut vertex = start;
+    visited[start] = true;
+    let mut weights = Vec::with_capacity(config.candidates);
+
+    for _ in 1..size {
+        weights.clear();
+        weights.extend(pheromone.candidates[vertex].iter().zip(pheromone.trail[vertex].iter())
+            .filter(|&(&u, _)| !visited[u])
+            .map(|(&u, &trail)| (u, trail.powf(config.alpha) * (1.0 / (tsp[(vertex, u)].to_f64() + 1e-9)).powf(config.beta))));
+
+        let next = if weights.is_empty() {
+            (0..size).filter(|&u| !visited[u])
+                .min_by(|&a, &b| tsp[(vertex, a)].partial_cmp(&tsp[(vertex, b)]).unwrap())
+                .unwrap()
+        } else {
+            let total: f64 = weights.iter().map(|&(_, w)| w).sum();
+            let mut target = random.unit() * total;
+            weights.iter()
+                .find(|&&(_, w)| { target -= w; target < 0.0 })
+                .unwrap_or(&weights[weights.len() - 1]).0
+        };
+
+        path.init_edge(vertex, next);
+        visited[next] = true;
+        vertex = next;
+    }
+    path.init_edge(vertex, start);
+
+    debug_assert!(path.is_hamiltonian());
+    Route::new(tsp.cost(&path), path)
+}
+
+/// MAX–MIN ant system. At each of the `steps` iterations the ants build their routes in parallel,
+/// the pheromone evaporates and the best route of the iteration deposits on its edges.
+/// Every ten iterations the best route found so far deposits instead.
+pub fn aco<W: Weight>(tsp: &SymmetricMatrix<W>, steps: usize, config: &AcoConfig) -> Route<W> {
+    let size = tsp.size();
+    assert!(size > 3 && config.ants > 0);
+
+    let mut best = tsp.nearest_neighbor();
+    let mut pheromone = Pheromone::new(tsp.candidates(config.candidates), config, best.cost.to_f64());
+
+    for step in 0..steps {
+        // Each ant has its own generator, so the routes don't depend on the scheduling of the threads.
+        let routes: Vec<Route<W>> = (0..config.ants)
+            .into_par_iter()
+            .map(|ant| {
+                let mut random = Random::new(config.seed ^ mix((step * config.ants + ant) as u64));
+                let mut route = construct(tsp, &pheromone, config, &mut random);
+                if config.local_search {
+                    local_search(tsp, &mut route, &mut Vec::with_capacity(size));
+                }
+                route
+            })
+            .collect();
+
+        let iteration_best = routes.into_iter().min_by(|a, b| a.cost.partial_cmp(&b.cost).unwrap()).unwrap();
+        if iteration_best.cost < best.cost {
+            best = iteration_best.clone();
+            pheromone.bound(config, best.cost.to_f64());
+        }
+
+        let deposit = if step % 10 == 9 { &best } else { &iteration_best };
+        pheromone.update(config, deposit);
+    }
+
+    local_search(tsp, &mut best, &mut Vec::with_capacity(size));
+
+    assert!(best.path.is_hamiltonian());
+    debug_assert!(best.check_cost(tsp));
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::aco::{AcoConfig, Pheromone};
+    use crate::fixture::scattered;
+
+    #[test]
+    fn bounds() {
+        let tsp = scattered::<f64>(40);
+        let config = AcoConfig { evaporation: 0.1, ..AcoConfig::default() };
+        let mut pheromone = Pheromone::new(tsp.candidates(config.candidates), &config, 500.0);
+
+        // Every edge starts at the upper bound, which goes up as the best route gets cheaper.
+        assert_eq!(pheromone.max, 1.0 / 50.0);
+        assert!(0.0 < pheromone.min && pheromone.min < pheromone.max);
+        assert!(pheromone.trail.iter().flatten().all(|&t| t == pheromone.max));
+        pheromone.bound(&config, 250.0);
+        assert_eq!(pheromone.max, 1.0 / 25.0);
+
+        // A much stronger deposit is capped, and the trails left alone evaporate down to the lower bound.
+        let edge = (0, pheromone.candidates[0][0]);
+        for _ in 0..200 {
+            pheromone.evaporate(config.evaporation);
+            pheromone.deposit(edge, 1.0);
+            pheromone.clamp();
+        }
+        let (min, max) = (pheromone.min, pheromone.max);
+        assert!(pheromone.trail.iter().flatten().all(|&t| min <= t && t <= max));
+        assert_eq!(pheromone.trail[0][0], max);
+        assert_eq!(pheromone.trail[0][1], min);
+
+        // Both directions of the edge, if the ends are candidates of each other.
+        let back = pheromone.candidates[edge.1].iter().position(|&u| u == 0);
+        assert!(back.is_none_or(|k| pheromone.trail[edge.1][k] == max));
+    }
+
+    #[test]
+    fn update() {
+        let tsp = scattered::<f64>(40);
+        let config = AcoConfig { evaporation: 0.1, ..AcoConfig::default() };
+        let best = tsp.nearest_neighbor();
+
+        // Bounds for a best route twice as cheap, so the deposit isn't capped.
+        let mut pheromone = Pheromone::new(tsp.candidates(config.candidates), &config, best.cost / 2.0);
+        let (min, max) = (pheromone.min, pheromone.max);
+        pheromone.update(&config, &best);
+
+        // The edges of the route get the deposit, the others only evaporate.
+        let edges: HashSet<_> = best.path.edges_visited().flat_map(|(v0, v1)| vec![(v0, v1), (v1, v0)]).collect();
+        for (v, candidates) in pheromone.candidates.iter().enumerate() {
+            for (k, &u) in candidates.iter().enumerate() {
+                let mut expected = max * (1.0 - config.evaporation);
+                if edges.contains(&(v, u)) { expected += 1.0 / best.cost; }
+                assert!((pheromone.trail[v][k] - expected.clamp(min, max)).abs() < 1e-12);
+            }
+        }
+        assert!(pheromone.trail.iter().flatten().any(|&t| t > max * (1.0 - config.evaporation)));
+    }
+}
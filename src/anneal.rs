@@ -0,0 +1,195 @@
+use crate::matrix::SymmetricMatrix;
+use crate::random::Random;
+use crate::route::Route;
+use crate::weight::Weight;
+use crate::local_search;
+
+/// How the temperature goes down after each step.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Cooling {
+    /// `t = alpha * t`.
+    Geometric { alpha: f64 },
+    /// `t = t / (1 + beta * t)`, as proposed by Lundy and Mees.
+    LundyMees { beta: f64 },
+    /// Geometric, going back to `factor` times the initial temperature after `patience` steps without a better route.
+    Reheating { alpha: f64, patience: usize, factor: f64 },
+}
+
+impl Cooling {
+    /// Temperature of the next step, after `since_best` steps without a better route. Reheating resets the count.
+    fn next(self, temperature: f64, initial: f64, since_best: &mut usize) -> f64 {
+        match self {
+            Cooling::Geometric { alpha } => alpha * temperature,
+            Cooling::LundyMees { beta } => temperature / (1.0 + beta * temperature),
+            Cooling::Reheating { patience, factor, .. } if *since_best >= patience => {
+                *since_best = 0;
+                factor * initial
+            }
+            Cooling::Reheating { alpha, .. } => alpha * temperature,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct AnnealConfig {
+    /// Probability of accepting the average worsening move at the initial temperature.
+    pub initial_acceptance: f64,
+    pub cooling: Cooling,
+    /// Moves tried at each temperature are this times the size of the problem.
+    pub moves_per_vertex: usize,
+    pub seed: u64,
+}
+
+impl Default for AnnealConfig {
+    fn default() -> Self {
+        Self { initial_acceptance: 0.5, cooling: Cooling::Geometric { alpha: 0.95 }, moves_per_vertex: 10, seed: 0 }
+    }
+}
+
+/// Random twist of two edges that don't share a vertex, in the order the route visits them.
+fn random_move(edges: &[(usize, usize)], random: &mut Random) -> Option<((usize, usize), (usize, usize))> {
+    let size = edges.len();
+    let (i, j) = (random.below(size), random.below(size));
+    let (i, j) = (i.min(j), i.max(j));
+
+    if j - i < 2 || (i == 0 && j == size - 1) {
+        return None;
+    }
+    Some((edges[i], edges[j]))
+}
+
+fn delta<W: Weight>(tsp: &SymmetricMatrix<W>, (a0, a1): (usize, usize), (b0, b1): (usize, usize)) -> f64 {
+    (tsp[(a0, b0)] + tsp[(a1, b1)]).to_f64() - (tsp[(a0, a1)] + tsp[(b0, b1)]).to_f64()
+}
+
+/// Metropolis criterion: improving moves are always accepted, worsening ones with probability `exp(-delta / temperature)`.
+#[inline]
+fn accepts(delta: f64, temperature: f64, random: &mut Random) -> bool {
+    delta < 0.0 || random.unit() < (-delta / temperature).exp()
+}
+
+/// Temperature at which the average worsening move among some random ones is accepted with the given probability.
+fn initial_temperature<W: Weight>(tsp: &SymmetricMatrix<W>, edges: &[(usize, usize)], acceptance: f64, random: &mut Random) -> f64 {
+    let worsening: Vec<_> = (0..edges.len())
+        .filter_map(|_| random_move(edges, random))
+        .map(|(a, b)| delta(tsp, a, b))
+        .filter(|&d| d > 0.0)
+        .collect();
+
+    if worsening.is_empty() {
+        return 1.0;
+    }
+    let average = worsening.iter().sum::<f64>() / worsening.len() as f64;
+    -average / acceptance.ln()
+}
+
+/// Simulated annealing over random twists, starting from the nearest neighbor route.
+/// Each of the `steps` steps tries a fixed number of moves at one temperature, so budgets compare with the ones of `gls`.
+/// The best route found is brought to a local minimum before being returned.
+pub fn anneal<W: Weight>(tsp: &SymmetricMatrix<W>, steps: usize, config: &AnnealConfig) -> Route<W> {
+    let size = tsp.size();
+    assert!(0.0 < config.initial_acceptance && config.initial_acceptance < 1.0);
+
+    let mut random = Random::new(config.seed);
+    let mut route = tsp.nearest_neighbor();
+    let mut best = route.clone();
+
+    let mut edge_buffer = Vec::with_capacity(size);
+    route.path.edges_visited_buffered(&mut edge_buffer);
+
+    let initial = initial_temperature(tsp, &edge_buffer, config.initial_acceptance, &mut random);
+    let mut temperature = initial;
+    let mut since_best = 0;
+
+    for _ in 0..steps {
+        let mut improved = false;
+
+        for _ in 0..config.moves_per_vertex * size {
+            let (a, b) = match random_move(&edge_buffer, &mut random) {
+                Some(m) => m,
+                None => continue,
+            };
+
+            let delta = delta(tsp, a, b);
+            if accepts(delta, temperature, &mut random) {
+                route.twist(tsp, a, b);
+                route.path.edges_visited_buffered(&mut edge_buffer);
+
+                if route.cost < best.cost {
+                    best = route.clone();
+                    improved = true;
+                }
+            }
+        }
+
+        since_best = if improved { 0 } else { since_best + 1 };
+        temperature = config.cooling.next(temperature, initial, &mut since_best);
+    }
+
+    local_search(tsp, &mut best, &mut edge_buffer);
+
+    assert!(best.path.is_hamiltonian());
+    debug_assert!(best.check_cost(tsp));
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::anneal::{accepts, delta, initial_temperature, random_move, Cooling};
+    use crate::fixture::scattered;
+    use crate::random::Random;
+
+    #[test]
+    fn schedules() {
+        let mut since_best = 3;
+        assert_eq!(Cooling::Geometric { alpha: 0.5 }.next(8.0, 100.0, &mut since_best), 4.0);
+        assert_eq!(Cooling::LundyMees { beta: 0.25 }.next(8.0, 100.0, &mut since_best), 8.0 / 3.0);
+
+        // Cools down until it has waited long enough, then starts over from a fraction of the initial temperature.
+        let reheating = Cooling::Reheating { alpha: 0.5, patience: 4, factor: 0.2 };
+        assert_eq!(reheating.next(8.0, 100.0, &mut since_best), 4.0);
+        assert_eq!(since_best, 3);
+        since_best = 4;
+        assert_eq!(reheating.next(8.0, 100.0, &mut since_best), 20.0);
+        assert_eq!(since_best, 0);
+    }
+
+    #[test]
+    fn initial_acceptance() {
+        let tsp = scattered::<f64>(40);
+        let mut edges = vec![];
+        tsp.nearest_neighbor().path.edges_visited_buffered(&mut edges);
+
+        let temperature = initial_temperature(&tsp, &edges, 0.3, &mut Random::new(5));
+
+        // The same moves, again.
+        let mut random = Random::new(5);
+        let worsening: Vec<_> = (0..edges.len())
+            .filter_map(|_| random_move(&edges, &mut random))
+            .map(|(a, b)| delta(&tsp, a, b))
+            .filter(|&d| d > 0.0)
+            .collect();
+        let average = worsening.iter().sum::<f64>() / worsening.len() as f64;
+
+        assert!((f64::exp(-average / temperature) - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn acceptance() {
+        let mut random = Random::new(9);
+
+        // At the temperature where a worsening of 10 is accepted with probability 0.25.
+        let temperature = -10.0 / f64::ln(0.25);
+        let accepted = (0..100_000).filter(|_| accepts(10.0, temperature, &mut random)).count();
+        assert!((accepted as f64 / 100_000.0 - 0.25).abs() < 0.01, "{}", accepted);
+
+        // Twice the worsening, the probability squared.
+        let accepted = (0..100_000).filter(|_| accepts(20.0, temperature, &mut random)).count();
+        assert!((accepted as f64 / 100_000.0 - 0.0625).abs() < 0.005, "{}", accepted);
+
+        // Improvements always, worsening never once frozen.
+        assert!((0..1000).all(|_| accepts(-1e-9, 1e-12, &mut random)));
+        assert!((0..1000).all(|_| !accepts(1.0, 1e-12, &mut random)));
+    }
+}
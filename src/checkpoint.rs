@@ -0,0 +1,185 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use crate::config::GlsConfig;
+use crate::matrix::SymmetricMatrix;
+use crate::path::Path;
+use crate::penalty::{Augmented, Penalties};
+use crate::route::Route;
+use crate::trace::Observer;
+use crate::weight::Weight;
+use crate::local_search_observed;
+
+/// State of a guided local search between two steps. It's all that's needed to resume the search.
+#[derive(PartialEq, Clone)]
+pub struct GlsState<W = u32> {
+    pub route: Route<W>,
+    /// Cheapest route found so far.
+    pub best: Route<W>,
+    pub penalties: Penalties,
+    pub penalty_factor: W,
+    /// Steps done so far.
+    pub step: usize,
+}
+
+const MAGIC: u64 = u64::from_le_bytes(*b"LKCP\0\0\0\0");
+
+impl<W: Weight> GlsState<W> {
+    /// State before the first step: a local minimum from the nearest neighbor route, without penalties.
+    pub fn new(tsp: &SymmetricMatrix<W>, config: &GlsConfig) -> Self {
+        Self::start(tsp, config, &mut ())
+    }
+
+    pub(crate) fn start<O: Observer>(tsp: &SymmetricMatrix<W>, config: &GlsConfig, observer: &mut O) -> Self {
+        Self::from_route(tsp, tsp.nearest_neighbor(), config, observer)
+    }
+
+    /// State before the first step from another initial route.
+    pub(crate) fn from_route<O: Observer>(tsp: &SymmetricMatrix<W>, mut route: Route<W>, config: &GlsConfig, observer: &mut O) -> Self {
+        let size = tsp.size();
+        observer.start(&route.path);
+
+        let mut edge_buffer = Vec::with_capacity(size);
+        local_search_observed(tsp, tsp, &mut route, &mut edge_buffer, observer);
+        observer.best();
+
+        let penalty_factor = W::from_f64(config.lambda * (route.cost.to_f64() / size as f64));
+        Self { best: route.clone(), route, penalties: Penalties::new(size), penalty_factor, step: 0 }
+    }
+
+    /// Weights plus penalties, as seen by the local search.
+    pub fn augmented<'a>(&'a self, tsp: &'a SymmetricMatrix<W>) -> Augmented<'a, W> {
+        Augmented::new(tsp, &self.penalties, self.penalty_factor)
+    }
+
+    /// Binary encoding, all little endian: the size, the step, the penalty factor,
+    /// the cost and adjacency of both routes and the penalized edges.
+    pub fn write<T: Write>(&self, mut writer: T) -> Result<(), String> {
+        let mut put = |value: u64| writer.write_all(&value.to_le_bytes()).map_err(|e| e.to_string());
+
+        put(MAGIC)?;
+        put(self.penalties.size() as u64)?;
+        put(self.step as u64)?;
+        put(self.penalty_factor.to_bits())?;
+
+        for route in [&self.route, &self.best].iter() {
+            put(route.cost.to_bits())?;
+            for v in 0..route.path.size() {
+                let (v0, v1) = route.path[v];
+                put(v0 as u64)?;
+                put(v1 as u64)?;
+            }
+        }
+
+        put(self.penalties.len() as u64)?;
+        for ((i, j), penalty) in self.penalties.penalized() {
+            put(i as u64)?;
+            put(j as u64)?;
+            put(penalty as u64)?;
+        }
+
+        writer.flush().map_err(|e| e.to_string())
+    }
+
+    /// Reads a state written by `write`, checking that the routes and penalties are valid.
+    pub fn read<T: Read>(mut reader: T) -> Result<Self, String> {
+        let mut get = || {
+            let mut bytes = [0u8; 8];
+            reader.read_exact(&mut bytes).map(|_| u64::from_le_bytes(bytes)).map_err(|e| e.to_string())
+        };
+
+        if get()? != MAGIC {
+            return Err("not a checkpoint".to_owned());
+        }
+
+        let size = get()? as usize;
+        let step = get()? as usize;
+        let penalty_factor = W::from_bits(get()?);
+
+        let mut routes = Vec::with_capacity(2);
+        for name in ["route", "best"].iter() {
+            let cost = W::from_bits(get()?);
+            let data = (0..size)
+                .map(|_| Ok((get()? as usize, get()? as usize)))
+                .collect::<Result<_, String>>()?;
+
+            let path = Path::try_new(data).map_err(|e| format!("{}: {}", name, e))?;
+            routes.push(Route::new(cost, path));
+        }
+
+        let mut penalties = Penalties::new(size);
+        let count = get()?;
+        for _ in 0..count {
+            let (i, j, penalty) = (get()? as usize, get()? as usize, get()?);
+            if i >= j || j >= size || penalty == 0 || penalty > u32::MAX as u64 {
+                return Err(format!("invalid penalty {} of ({}, {})", penalty, i, j));
+            }
+            penalties.set((i, j), penalty as u32);
+        }
+
+        let best = routes.pop().unwrap();
+        let route = routes.pop().unwrap();
+        Ok(Self { route, best, penalties, penalty_factor, step })
+    }
+
+    /// Writes to a temporary file first, so an interruption never leaves a broken checkpoint behind.
+    pub fn save(&self, file: &str) -> Result<(), String> {
+        let tmp = format!("{}.tmp", file);
+        let writer = File::create(&tmp).map_err(|e| format!("{}: {}", tmp, e))?;
+        self.write(BufWriter::new(writer))?;
+        fs::rename(&tmp, file).map_err(|e| format!("{}: {}", file, e))
+    }
+
+    pub fn load(file: &str) -> Result<Self, String> {
+        let reader = File::open(file).map_err(|e| format!("{}: {}", file, e))?;
+        Self::read(BufReader::new(reader))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checkpoint::GlsState;
+    use crate::config::GlsConfig;
+    use crate::fixture::scattered;
+    use crate::matrix::SymmetricMatrix;
+    use crate::{gls_with_checkpoints, gls_resume};
+
+    fn matrix() -> SymmetricMatrix<f64> {
+        scattered(40)
+    }
+
+    #[test]
+    fn resume() {
+        let tsp = matrix();
+
+        let mut checkpoints = vec![];
+        let uninterrupted = gls_with_checkpoints(&tsp, 30, &GlsConfig::default(), 10, |state| {
+            let mut bytes = vec![];
+            state.write(&mut bytes).unwrap();
+            checkpoints.push(bytes);
+        });
+        assert_eq!(checkpoints.len(), 3);
+
+        let state = GlsState::<f64>::read(&checkpoints[0][..]).unwrap();
+        assert_eq!(state.step, 10);
+        assert!(!state.penalties.is_empty());
+
+        let mut saved = vec![];
+        let resumed = gls_resume(&tsp, state, 30, &GlsConfig::default(), 10, |state| saved.push(state.clone()));
+        assert_eq!(resumed, uninterrupted);
+        assert!(saved[1] == GlsState::read(&checkpoints[2][..]).unwrap());
+    }
+
+    #[test]
+    fn invalid() {
+        let tsp = matrix();
+        let mut bytes = vec![];
+        GlsState::new(&tsp, &GlsConfig::default()).write(&mut bytes).unwrap();
+
+        assert!(GlsState::<f64>::read(&b"LKTR\0\0\0\0"[..]).err().unwrap() == "not a checkpoint");
+        assert!(GlsState::<f64>::read(&bytes[..bytes.len() - 1]).is_err());
+
+        // The first vertex of the route points out of range.
+        bytes[48..56].copy_from_slice(&99u64.to_le_bytes());
+        assert!(GlsState::<f64>::read(&bytes[..]).err().unwrap() == "route: vertex 99 is out of range");
+    }
+}
@@ -0,0 +1,127 @@
+use crate::random::mix;
+
+/// How much an edge of the local minimum is worth penalizing, given its weight and current penalty.
+#[derive(Debug, Copy, Clone)]
+pub enum Utility {
+    /// `weight / (1 + penalty)`, as in the original guided local search.
+    Standard,
+    /// Only the weight: the longest edges are penalized every time.
+    Weight,
+    Custom(fn(f64, u32) -> f64),
+}
+
+impl Utility {
+    #[inline]
+    pub fn eval(self, weight: f64, penalty: u32) -> f64 {
+        match self {
+            Utility::Standard => weight / (1.0 + penalty as f64),
+            Utility::Weight => weight,
+            Utility::Custom(f) => f(weight, penalty),
+        }
+    }
+}
+
+/// Which edges are penalized when several have the maximum utility.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum TieBreak {
+    All,
+    /// The first in the order the route visits them, starting from 0.
+    First,
+    /// One of them, chosen by a generator seeded with the seed and the step so runs are still deterministic.
+    Random(u64),
+}
+
+/// Periodic change of the penalties, to forget old local minima. It happens before the penalties of the next step.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Decay {
+    None,
+    /// Every `every` steps, multiply every penalty by `factor`, rounding down.
+    Scale { every: usize, factor: f64 },
+    /// Every `every` steps, drop all penalties.
+    Reset { every: usize },
+}
+
+/// Parameters of the guided local search. The default is the guided fast local search as originally published.
+#[derive(Debug, Copy, Clone)]
+pub struct GlsConfig {
+    /// λ, the weight of a unit of penalty, is this coefficient times the cost of the first local minimum over its size.
+    pub lambda: f64,
+    pub utility: Utility,
+    pub tie_break: TieBreak,
+    /// Added to the penalty of each edge chosen.
+    pub increment: u32,
+    pub decay: Decay,
+    /// Accept moves that lead to a route cheaper than the best one, whatever their penalties.
+    pub aspiration: bool,
+    /// After each penalty, search only around the edges whose penalty changed, as in the guided fast local search.
+    /// Otherwise every pair of edges of the route is tried again.
+    pub fast: bool,
+    /// Nearest vertices the fast local search tries to link each vertex to.
+    pub candidates: usize,
+}
+
+impl Default for GlsConfig {
+    fn default() -> Self {
+        Self { lambda: 0.3, utility: Utility::Standard, tie_break: TieBreak::All, increment: 1, decay: Decay::None, aspiration: false, fast: true, candidates: 10 }
+    }
+}
+
+impl GlsConfig {
+    /// Edges to penalize among the ones of the route, given their utilities.
+    pub(crate) fn choose(&self, edges: &[(usize, usize)], utilities: &[f64], step: usize) -> Vec<(usize, usize)> {
+        let max_utility = utilities.iter().copied().fold(f64::MIN, f64::max);
+        let mut ties = edges.iter().zip(utilities).filter(|&(_, &u)| u == max_utility).map(|(&e, _)| e);
+
+        match self.tie_break {
+            TieBreak::All => ties.collect(),
+            TieBreak::First => ties.next().into_iter().collect(),
+            TieBreak::Random(seed) => {
+                let ties: Vec<_> = ties.collect();
+                let i = mix(seed ^ mix(step as u64)) % ties.len() as u64;
+                vec![ties[i as usize]]
+            }
+        }
+    }
+
+    /// Whether the penalties decay after the given number of steps.
+    pub(crate) fn decays_after(&self, step: usize) -> bool {
+        match self.decay {
+            Decay::None => false,
+            Decay::Scale { every, .. } | Decay::Reset { every } => every > 0 && step.is_multiple_of(every),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{Decay, GlsConfig, TieBreak, Utility};
+
+    const EDGES: [(usize, usize); 4] = [(0, 1), (1, 2), (2, 3), (3, 0)];
+
+    #[test]
+    fn ties() {
+        let utilities = [2.0, 5.0, 1.0, 5.0];
+        let config = |tie_break| GlsConfig { tie_break, ..GlsConfig::default() };
+
+        assert_eq!(config(TieBreak::All).choose(&EDGES, &utilities, 0), vec![(1, 2), (3, 0)]);
+        assert_eq!(config(TieBreak::First).choose(&EDGES, &utilities, 0), vec![(1, 2)]);
+
+        let random = config(TieBreak::Random(7));
+        let chosen: Vec<_> = (0..20).map(|step| random.choose(&EDGES, &utilities, step)[0]).collect();
+        assert!(chosen.iter().all(|e| [(1, 2), (3, 0)].contains(e)));
+        assert!(chosen.contains(&(1, 2)) && chosen.contains(&(3, 0)));
+        assert_eq!(random.choose(&EDGES, &utilities, 3), random.choose(&EDGES, &utilities, 3));
+    }
+
+    #[test]
+    fn utility() {
+        assert_eq!(Utility::Standard.eval(10.0, 4), 2.0);
+        assert_eq!(Utility::Weight.eval(10.0, 4), 10.0);
+        assert_eq!(Utility::Custom(|w, p| w - p as f64).eval(10.0, 4), 6.0);
+
+        let config = GlsConfig { decay: Decay::Reset { every: 5 }, ..GlsConfig::default() };
+        assert!(!config.decays_after(4));
+        assert!(config.decays_after(10));
+        assert!(!GlsConfig::default().decays_after(10));
+    }
+}
@@ -0,0 +1,229 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use crate::matrix::SymmetricMatrix;
+use crate::weight::{Weight, WeightError};
+
+/// Layout of a coordinate file.
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum Format {
+    /// `NODE_COORD_SECTION` of a TSPLIB file with `EDGE_WEIGHT_TYPE : EUC_2D`.
+    Tsplib,
+    /// One `x,y` pair per line. A header line is skipped.
+    Csv,
+    /// One `x y` pair per line.
+    Whitespace,
+}
+
+impl Format {
+    /// Guesses the format from the extension of the file: `.tsp`, `.csv` or anything else.
+    pub fn from_extension(file: &str) -> Self {
+        match file.rsplit('.').next() {
+            Some("tsp") => Format::Tsplib,
+            Some("csv") => Format::Csv,
+            _ => Format::Whitespace,
+        }
+    }
+}
+
+/// Points of a Euclidean instance, without the distances between them.
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct Coordinates {
+    points: Vec<(i32, i32)>,
+}
+
+impl Coordinates {
+    pub fn new(points: Vec<(i32, i32)>) -> Self {
+        Self { points }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn points(&self) -> &[(i32, i32)] {
+        &self.points
+    }
+
+    /// Full distance matrix. Only viable for small instances as it takes quadratic memory.
+    pub fn to_matrix<W: Weight>(&self) -> Result<SymmetricMatrix<W>, WeightError> {
+        SymmetricMatrix::try_from_euc_2d(&self.points)
+    }
+}
+
+/// Parses a coordinate as TSPLIB does: a real number truncated to an integer.
+pub(crate) fn parse_coord(token: &str) -> Result<i32, String> {
+    match token.parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= i32::MIN as f64 && value <= i32::MAX as f64 => Ok(value as i32),
+        Ok(_) => Err(format!("coordinate {} is out of range", token)),
+        Err(_) => Err(format!("invalid coordinate {:?}", token)),
+    }
+}
+
+/// Parses a line with exactly two coordinates.
+fn parse_point<'a>(mut tokens: impl Iterator<Item=&'a str>) -> Result<(i32, i32), String> {
+    match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some(x), Some(y), None) => Ok((parse_coord(x.trim())?, parse_coord(y.trim())?)),
+        _ => Err("expected 2 coordinates".to_owned()),
+    }
+}
+
+struct TsplibReader {
+    in_coords: bool,
+    dimension: Option<usize>,
+    points: Vec<Option<(i32, i32)>>,
+}
+
+impl TsplibReader {
+    fn line(&mut self, line: &str) -> Result<(), String> {
+        if line.starts_with("NODE_COORD_SECTION") {
+            self.dimension.ok_or("missing DIMENSION before NODE_COORD_SECTION")?;
+            self.in_coords = true;
+            return Ok(());
+        }
+
+        if !self.in_coords {
+            let mut kv = line.splitn(2, ':');
+            let key = kv.next().unwrap().trim();
+            let value = kv.next().ok_or("expected `KEY : VALUE`")?.trim();
+
+            match key {
+                "TYPE" if value != "TSP" => return Err(format!("unsupported type {}", value)),
+                "EDGE_WEIGHT_TYPE" if value != "EUC_2D" => return Err(format!("unsupported edge weight {}", value)),
+                "DIMENSION" => self.dimension = Some(value.parse::<usize>().map_err(|e| e.to_string())?),
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        let mut tokens = line.split_whitespace();
+        let id = tokens.next().unwrap();
+        let id = id.parse::<usize>().map_err(|_| format!("invalid vertex {:?}", id))?;
+        if id == 0 || id > self.dimension.unwrap() {
+            return Err(format!("vertex {} is out of range", id));
+        }
+
+        // The points grow as they are read since DIMENSION may be way off.
+        if id > self.points.len() {
+            self.points.resize(id, None);
+        }
+
+        let point = parse_point(tokens)?;
+        match &mut self.points[id - 1] {
+            Some(_) => Err(format!("vertex {} is repeated", id)),
+            slot => {
+                *slot = Some(point);
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(self) -> Result<Vec<(i32, i32)>, String> {
+        if !self.in_coords {
+            return Err("missing NODE_COORD_SECTION".to_owned());
+        }
+
+        (0..self.dimension.unwrap())
+            .map(|v| self.points.get(v).copied().flatten().ok_or_else(|| format!("vertex {} has no coordinates", v + 1)))
+            .collect()
+    }
+}
+
+/// Reads the coordinates one line at a time, so the only memory used is the one for the points.
+/// Errors are reported with the number of the line they happened on.
+pub fn read<R: BufRead>(mut reader: R, format: Format) -> Result<Coordinates, String> {
+    let mut line = String::new();
+    let mut line_number = 0;
+    let mut points = vec![];
+    let mut tsplib = TsplibReader { in_coords: false, dimension: None, points: vec![] };
+
+    loop {
+        line.clear();
+        line_number += 1;
+        let err = |msg: String| format!("line {}: {}", line_number, msg);
+
+        if reader.read_line(&mut line).map_err(|e| err(e.to_string()))? == 0 { break; }
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() { continue; }
+
+        match format {
+            Format::Tsplib => {
+                if trimmed == "EOF" { break; }
+                tsplib.line(trimmed).map_err(err)?;
+            }
+            Format::Csv => match parse_point(trimmed.split(',')) {
+                Ok(point) => points.push(point),
+                // A header names the columns.
+                Err(_) if line_number == 1 && !trimmed.starts_with(|c: char| c.is_ascii_digit() || c == '-') => {}
+                Err(e) => return Err(err(e)),
+            },
+            Format::Whitespace => points.push(parse_point(trimmed.split_whitespace()).map_err(err)?),
+        }
+    }
+
+    if format == Format::Tsplib {
+        points = tsplib.finish()?;
+    }
+
+    Ok(Coordinates::new(points))
+}
+
+/// Reads a coordinate file guessing its format from the extension. See `Format::from_extension`.
+pub fn from_file(file: &str) -> Result<Coordinates, String> {
+    let reader = File::open(file).map_err(|e| format!("{}: {}", file, e))?;
+    read(BufReader::new(reader), Format::from_extension(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coords::{read, Format};
+
+    const TSPLIB: &str = "\
+NAME : test
+TYPE : TSP
+DIMENSION : 3
+EDGE_WEIGHT_TYPE : EUC_2D
+NODE_COORD_SECTION
+2 3.0e+01 4.5
+1 0 0
+3 -10 20
+EOF
+";
+
+    #[test]
+    fn tsplib() {
+        let coords = read(TSPLIB.as_bytes(), Format::Tsplib).unwrap();
+        assert_eq!(coords.points(), &[(0, 0), (30, 4), (-10, 20)]);
+        assert_eq!(coords.to_matrix::<u32>().unwrap()[(0, 1)], 30);
+
+        let repeated = TSPLIB.replace("3 -10", "2 -10");
+        assert_eq!(read(repeated.as_bytes(), Format::Tsplib).err().unwrap(), "line 8: vertex 2 is repeated");
+
+        let short = TSPLIB.replace("DIMENSION : 3", "DIMENSION : 4");
+        assert_eq!(read(short.as_bytes(), Format::Tsplib).err().unwrap(), "vertex 4 has no coordinates");
+
+        // Nothing is allocated for the vertices that are never read.
+        let huge = TSPLIB.replace("DIMENSION : 3", "DIMENSION : 18446744073709551615");
+        assert_eq!(read(huge.as_bytes(), Format::Tsplib).err().unwrap(), "vertex 4 has no coordinates");
+
+        let att = TSPLIB.replace("EUC_2D", "ATT");
+        assert_eq!(read(att.as_bytes(), Format::Tsplib).err().unwrap(), "line 4: unsupported edge weight ATT");
+    }
+
+    #[test]
+    fn plain() {
+        let coords = read("x,y\n1,2\n\n3, 4\n".as_bytes(), Format::Csv).unwrap();
+        assert_eq!(coords.points(), &[(1, 2), (3, 4)]);
+
+        let coords = read("1 2\n-3\t4.9\n".as_bytes(), Format::Whitespace).unwrap();
+        assert_eq!(coords.points(), &[(1, 2), (-3, 4)]);
+
+        assert_eq!(read("1,2\n3\n".as_bytes(), Format::Csv).err().unwrap(), "line 2: expected 2 coordinates");
+        assert_eq!(read("1 2\n3 y\n".as_bytes(), Format::Whitespace).err().unwrap(), "line 2: invalid coordinate \"y\"");
+        assert_eq!(read("1 3e10\n".as_bytes(), Format::Whitespace).err().unwrap(), "line 1: coordinate 3e10 is out of range");
+    }
+}
@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use crate::matrix::SymmetricMatrix;
+use crate::path::Path;
+use crate::random::Random;
+use crate::route::Route;
+use crate::weight::Weight;
+use crate::local_search;
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct EaxConfig {
+    pub population: usize,
+    /// Children generated from each pair of parents, one per AB-cycle.
+    pub children: usize,
+    /// Size of the candidate lists used when seeding the population and joining subtours.
+    pub candidates: usize,
+    pub seed: u64,
+}
+
+impl Default for EaxConfig {
+    fn default() -> Self {
+        Self { population: 30, children: 20, candidates: 10, seed: 0 }
+    }
+}
+
+#[inline]
+fn key((v0, v1): (usize, usize)) -> (usize, usize) {
+    (v0.min(v1), v0.max(v1))
+}
+
+/// How many routes of the population have each edge, to measure its diversity.
+struct Frequency {
+    count: HashMap<(usize, usize), u32>,
+    population: f64,
+}
+
+impl Frequency {
+    fn new<W>(routes: &[Route<W>]) -> Self {
+        let mut count = HashMap::new();
+        for route in routes {
+            for edge in route.path.edges_visited() {
+                *count.entry(key(edge)).or_insert(0) += 1;
+            }
+        }
+        Self { count, population: routes.len() as f64 }
+    }
+
+    fn term(&self, count: u32) -> f64 {
+        if count == 0 { return 0.0; }
+        let p = count as f64 / self.population;
+        -p * p.ln()
+    }
+
+    fn get(&self, edge: (usize, usize)) -> u32 {
+        self.count.get(&key(edge)).copied().unwrap_or(0)
+    }
+
+    /// Change of the entropy of the population when a route loses the edges removed and gains the ones added.
+    fn delta(&self, removed: &[(usize, usize)], added: &[(usize, usize)]) -> f64 {
+        let removed: f64 = removed.iter().map(|&e| self.term(self.get(e) - 1) - self.term(self.get(e))).sum();
+        let added: f64 = added.iter().map(|&e| self.term(self.get(e) + 1) - self.term(self.get(e))).sum();
+        removed + added
+    }
+
+    fn replace(&mut self, removed: &[(usize, usize)], added: &[(usize, usize)]) {
+        for &e in removed {
+            *self.count.get_mut(&key(e)).unwrap() -= 1;
+        }
+        for &e in added {
+            *self.count.entry(key(e)).or_insert(0) += 1;
+        }
+    }
+}
+
+/// Splits the edges that are in only one of the paths into cycles alternating between an edge of `a` and one of `b`.
+/// In each cycle, the edge from a vertex at an even index to the next one is in `a`.
+fn ab_cycles(a: &Path, b: &Path, random: &mut Random) -> Vec<Vec<usize>> {
+    let size = a.size();
+    let only = |x: &Path, y: &Path, v: usize| -> Vec<usize> {
+        let ((x0, x1), (y0, y1)) = (x[v], y[v]);
+        [x0, x1].iter().copied().filter(|&u| u != y0 && u != y1).collect()
+    };
+    let mut edges: [Vec<Vec<usize>>; 2] = [(0..size).map(|v| only(a, b, v)).collect(), (0..size).map(|v| only(b, a, v)).collect()];
+    let remove = |edges: &mut Vec<Vec<usize>>, v0: usize, v1: usize| {
+        for &(x, y) in [(v0, v1), (v1, v0)].iter() {
+            let k = edges[x].iter().position(|&u| u == y).unwrap();
+            edges[x].swap_remove(k);
+        }
+    };
+
+    let mut cycles = vec![];
+    let mut trail = vec![];
+    // Even indices of each vertex in the trail, from where the walk leaves through an edge of `a`.
+    let mut even: Vec<Vec<usize>> = vec![vec![]; size];
+
+    for start in 0..size {
+        if edges[0][start].is_empty() { continue; }
+        trail.clear();
+        trail.push(start);
+        even[start].push(0);
+
+        loop {
+            let (k, v) = (trail.len() - 1, trail[trail.len() - 1]);
+            let kind = k % 2;
+            // The walk only stops back at the start, as every vertex has as many edges of `a` left as of `b`.
+            if kind == 0 && edges[0][v].is_empty() { break; }
+
+            let u = edges[kind][v][random.below(edges[kind][v].len())];
+            remove(&mut edges[kind], v, u);
+            trail.push(u);
+            if kind == 0 { continue; }
+
+            match even[u].last() {
+                Some(&p) => {
+                    let cycle = trail[p..trail.len() - 1].to_vec();
+                    for (i, &w) in cycle.iter().enumerate().skip(1) {
+                        if (p + i) % 2 == 0 { even[w].pop(); }
+                    }
+                    trail.truncate(p + 1);
+                    cycles.push(cycle);
+                }
+                None => even[u].push(k + 1),
+            }
+        }
+
+        even[start].pop();
+    }
+
+    cycles
+}
+
+#[inline]
+fn replace(adjacent: &mut [[usize; 2]], v: usize, old: usize, new: usize) {
+    let slots = &mut adjacent[v];
+    let k = if slots[0] == old { 0 } else { 1 };
+    debug_assert_eq!(slots[k], old);
+    slots[k] = new;
+}
+
+/// Vertices of each subtour, and the subtour of each vertex.
+fn subtours(adjacent: &[[usize; 2]]) -> (Vec<Vec<usize>>, Vec<usize>) {
+    let size = adjacent.len();
+    let mut label = vec![usize::MAX; size];
+    let mut members = vec![];
+
+    for start in 0..size {
+        if label[start] != usize::MAX { continue; }
+
+        let mut tour = vec![];
+        let (mut previous, mut vertex) = (adjacent[start][1], start);
+        loop {
+            label[vertex] = members.len();
+            tour.push(vertex);
+            let next = if adjacent[vertex][0] != previous { adjacent[vertex][0] } else { adjacent[vertex][1] };
+            previous = vertex;
+            vertex = next;
+            if vertex == start { break; }
+        }
+        members.push(tour);
+    }
+
+    (members, label)
+}
+
+/// Cost of exchanging two edges for the ones joining their ends, and the edges.
+type Exchange = (f64, (usize, usize), (usize, usize));
+
+/// Joins the subtours into a single one, each time merging the smallest with the neighbor that costs the least.
+fn join<W: Weight>(tsp: &SymmetricMatrix<W>, candidates: &[Vec<usize>], adjacent: &mut [[usize; 2]]) {
+    let (mut members, mut label) = subtours(adjacent);
+    let mut alive = members.len();
+
+    while alive > 1 {
+        let smallest = (0..members.len()).filter(|&t| !members[t].is_empty()).min_by_key(|&t| members[t].len()).unwrap();
+
+        let mut best = None;
+        let consider = |best: &mut Option<Exchange>, (u0, u1): (usize, usize), (v0, v1): (usize, usize)| {
+            let delta = (tsp[(u0, v0)] + tsp[(u1, v1)]).to_f64() - (tsp[(u0, u1)] + tsp[(v0, v1)]).to_f64();
+            if best.is_none_or(|(d, ..)| delta < d) {
+                *best = Some((delta, (u0, u1), (v0, v1)));
+            }
+        };
+
+        for &u0 in members[smallest].iter() {
+            for &u1 in adjacent[u0].iter() {
+                for &v0 in candidates[u0].iter().filter(|&&v| label[v] != smallest) {
+                    for &v1 in adjacent[v0].iter() {
+                        consider(&mut best, (u0, u1), (v0, v1));
+                    }
+                }
+            }
+        }
+
+        // Every candidate is in the subtour itself.
+        if best.is_none() {
+            let u0 = members[smallest][0];
+            let u1 = adjacent[u0][0];
+            for v0 in (0..adjacent.len()).filter(|&v| label[v] != smallest) {
+                for &v1 in adjacent[v0].iter() {
+                    consider(&mut best, (u0, u1), (v0, v1));
+                }
+            }
+        }
+
+        let (_, (u0, u1), (v0, v1)) = best.unwrap();
+        replace(adjacent, u0, u1, v0);
+        replace(adjacent, u1, u0, v1);
+        replace(adjacent, v0, v1, u0);
+        replace(adjacent, v1, v0, u1);
+
+        let target = label[v0];
+        let moved = std::mem::take(&mut members[smallest]);
+        for &v in moved.iter() {
+            label[v] = target;
+        }
+        members[target].extend(moved);
+        alive -= 1;
+    }
+}
+
+/// Child of a route, with the edges it lost and gained.
+struct Offspring<W> {
+    route: Route<W>,
+    removed: Vec<(usize, usize)>,
+    added: Vec<(usize, usize)>,
+}
+
+/// Neighbors of each vertex once the edges of `a` in the AB-cycle are replaced by the ones of `b`. May have subtours.
+fn intermediate(a: &Path, cycle: &[usize]) -> Vec<[usize; 2]> {
+    let mut adjacent: Vec<_> = (0..a.size()).map(|v| [a[v].0, a[v].1]).collect();
+
+    let len = cycle.len();
+    for (i, &v) in cycle.iter().enumerate() {
+        let (before, after) = (cycle[(i + len - 1) % len], cycle[(i + 1) % len]);
+        let (old, new) = if i % 2 == 0 { (after, before) } else { (before, after) };
+        replace(&mut adjacent, v, old, new);
+    }
+
+    adjacent
+}
+
+/// Child of `a` with the edges of `a` in the AB-cycle replaced by the ones of `b`, its subtours joined.
+fn offspring<W: Weight>(tsp: &SymmetricMatrix<W>, candidates: &[Vec<usize>], a: &Path, cycle: &[usize]) -> Offspring<W> {
+    let size = a.size();
+    let mut adjacent = intermediate(a, cycle);
+    join(tsp, candidates, &mut adjacent);
+
+    let (mut removed, mut added) = (vec![], vec![]);
+    for v in 0..size {
+        let (a0, a1) = a[v];
+        for &u in adjacent[v].iter().filter(|&&u| v < u && u != a0 && u != a1) {
+            added.push((v, u));
+        }
+        for &u in [a0, a1].iter().filter(|&&u| v < u && !adjacent[v].contains(&u)) {
+            removed.push((v, u));
+        }
+    }
+
+    let path = Path::new(adjacent.iter().map(|&[v0, v1]| (v0.min(v1), v0.max(v1))).collect());
+    Offspring { route: Route::new(tsp.cost(&path), path), removed, added }
+}
+
+/// Genetic algorithm with the edge assembly crossover. The population starts from randomized nearest neighbor routes
+/// brought to a local minimum. At each of the `steps` generations, every route is crossed with the next one in a random order,
+/// and replaced by the child that shortens it the most per unit of entropy lost by the population.
+/// Stops early once the population has converged to a single route.
+pub fn eax<W: Weight>(tsp: &SymmetricMatrix<W>, steps: usize, config: &EaxConfig) -> Route<W> {
+    let size = tsp.size();
+    assert!(config.population > 1);
+
+    let mut random = Random::new(config.seed);
+    let candidates = tsp.candidates(config.candidates);
+    let mut edge_buffer = Vec::with_capacity(size);
+
+    let mut population: Vec<_> = (0..config.population)
+        .map(|i| {
+            let mut route = if i == 0 { tsp.nearest_neighbor() } else { tsp.randomized_neighbor(&candidates, &mut random) };
+            local_search(tsp, &mut route, &mut edge_buffer);
+            route
+        })
+        .collect();
+    let mut frequency = Frequency::new(&population);
+
+    for _ in 0..steps {
+        let mut order: Vec<_> = (0..population.len()).collect();
+        for i in (1..order.len()).rev() {
+            order.swap(i, random.below(i + 1));
+        }
+
+        let mut converged = true;
+        for (k, &i) in order.iter().enumerate() {
+            let j = order[(k + 1) % order.len()];
+            let mut cycles = ab_cycles(&population[i].path, &population[j].path, &mut random);
+            if cycles.is_empty() { continue; }
+            converged = false;
+
+            for c in (1..cycles.len()).rev() {
+                cycles.swap(c, random.below(c + 1));
+            }
+
+            let mut chosen: Option<(f64, Offspring<W>)> = None;
+            for cycle in cycles.iter().take(config.children) {
+                let child = offspring(tsp, &candidates, &population[i].path, cycle);
+                if !W::improves(population[i].cost, child.route.cost) { continue; }
+
+                let shortening = (population[i].cost - child.route.cost).to_f64();
+                let entropy = frequency.delta(&child.removed, &child.added);
+                let score = if entropy >= 0.0 { shortening / 1e-9 } else { shortening / -entropy };
+                if chosen.as_ref().is_none_or(|(s, _)| score > *s) {
+                    chosen = Some((score, child));
+                }
+            }
+
+            if let Some((_, child)) = chosen {
+                frequency.replace(&child.removed, &child.added);
+                population[i] = child.route;
+            }
+        }
+
+        if converged { break; }
+    }
+
+    let mut best = population.into_iter()
+        .min_by(|a, b| a.cost.partial_cmp(&b.cost).unwrap())
+        .unwrap();
+    local_search(tsp, &mut best, &mut edge_buffer);
+
+    assert!(best.path.is_hamiltonian());
+    debug_assert!(best.check_cost(tsp));
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::eax::{ab_cycles, intermediate, key, offspring, subtours};
+    use crate::fixture::scattered;
+    use crate::local_search;
+    use crate::path::Path;
+    use crate::random::Random;
+
+    #[test]
+    fn cycles() {
+        let a = Path::from_order(&[0, 1, 2, 3, 4, 5, 6, 7]);
+        let b = Path::from_order(&[0, 2, 1, 3, 5, 4, 6, 7]);
+        let edges = |p: &Path| p.edges_visited().map(|(v0, v1)| (v0.min(v1), v0.max(v1))).collect::<HashSet<_>>();
+        let (ea, eb) = (edges(&a), edges(&b));
+
+        let cycles = ab_cycles(&a, &b, &mut Random::new(5));
+        let mut covered = vec![];
+        for cycle in cycles.iter() {
+            assert!(cycle.len() >= 4 && cycle.len() % 2 == 0);
+            for (i, &v) in cycle.iter().enumerate() {
+                let u = cycle[(i + 1) % cycle.len()];
+                let edge = (v.min(u), v.max(u));
+                assert!(if i % 2 == 0 { ea.contains(&edge) && !eb.contains(&edge) } else { eb.contains(&edge) && !ea.contains(&edge) });
+                covered.push(edge);
+            }
+        }
+
+        covered.sort_unstable();
+        let mut expected: Vec<_> = ea.symmetric_difference(&eb).copied().collect();
+        expected.sort_unstable();
+        assert_eq!(covered, expected);
+    }
+
+    #[test]
+    fn common_edges() {
+        let tsp = scattered::<f64>(40);
+        let candidates = tsp.candidates(10);
+        let mut random = Random::new(11);
+        let mut parent = || {
+            let mut route = tsp.randomized_neighbor(&candidates, &mut random);
+            local_search(&tsp, &mut route, &mut vec![]);
+            route.path
+        };
+        let (a, b) = (parent(), parent());
+
+        let edges = |p: &Path| p.edges_visited().map(key).collect::<HashSet<_>>();
+        let (ea, eb) = (edges(&a), edges(&b));
+        let common: HashSet<_> = ea.intersection(&eb).copied().collect();
+
+        let cycles = ab_cycles(&a, &b, &mut random);
+        assert!(!cycles.is_empty());
+        for cycle in cycles.iter() {
+            let child = offspring(&tsp, &candidates, &a, cycle);
+            assert!(child.route.path.is_hamiltonian());
+            assert!(child.route.check_cost(&tsp));
+
+            // Only joining the subtours of the intermediate solution may drop a common edge, two edges per join.
+            let (subtours, _) = subtours(&intermediate(&a, cycle));
+            let lost = common.difference(&edges(&child.route.path)).count();
+            assert!(lost <= 2 * (subtours.len() - 1));
+
+            // The edges of the cycle come from the other parent.
+            let len = cycle.len();
+            let from_b: Vec<_> = (1..len).step_by(2).map(|i| key((cycle[i], cycle[(i + 1) % len]))).collect();
+            assert!(from_b.iter().all(|e| eb.contains(e) && !ea.contains(e)));
+            if subtours.len() == 1 {
+                assert_eq!(lost, 0);
+                assert!(from_b.iter().all(|e| child.added.contains(e)));
+            }
+        }
+    }
+
+    #[test]
+    fn union() {
+        let tsp = scattered::<f64>(40);
+        let candidates = tsp.candidates(10);
+        let mut random = Random::new(3);
+        let mut parent = || {
+            let mut route = tsp.randomized_neighbor(&candidates, &mut random);
+            local_search(&tsp, &mut route, &mut vec![]);
+            route.path
+        };
+
+        let edges = |p: &Path| p.edges_visited().map(key).collect::<HashSet<_>>();
+        for seed in 0..5 {
+            let (a, b) = (parent(), parent());
+            let union: HashSet<_> = edges(&a).union(&edges(&b)).copied().collect();
+
+            for cycle in ab_cycles(&a, &b, &mut Random::new(seed)).iter() {
+                let child = offspring(&tsp, &candidates, &a, cycle);
+
+                // Edges from neither parent only come from joining subtours, two for each join.
+                let (subtours, _) = subtours(&intermediate(&a, cycle));
+                let new = edges(&child.route.path).difference(&union).count();
+                assert!(new <= 2 * (subtours.len() - 1));
+                if subtours.len() == 1 {
+                    assert!(edges(&child.route.path).is_subset(&union));
+                }
+            }
+        }
+    }
+}
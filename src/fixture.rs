@@ -0,0 +1,12 @@
+use crate::matrix::SymmetricMatrix;
+use crate::weight::Weight;
+
+/// Points scattered without any structure, for tests that need more than a handful of vertices.
+pub(crate) fn coords(size: usize) -> Vec<(i32, i32)> {
+    (0..size as i32).map(|i| ((i * 37) % 101, (i * i * 13) % 97)).collect()
+}
+
+/// Instance over the first `size` scattered points.
+pub(crate) fn scattered<W: Weight>(size: usize) -> SymmetricMatrix<W> {
+    SymmetricMatrix::from_euc_2d(&coords(size))
+}
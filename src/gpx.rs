@@ -0,0 +1,358 @@
+use std::collections::{HashSet, VecDeque};
+use crate::matrix::SymmetricMatrix;
+use crate::path::Path;
+use crate::route::Route;
+use crate::weight::Weight;
+
+/// Neighbors of the vertex in `x` that aren't its neighbors in `y`.
+fn only(x: &Path, y: &Path, v: usize) -> impl Iterator<Item=usize> {
+    let ((x0, x1), (y0, y1)) = (x[v], y[v]);
+    let x1 = if x1 != x0 { Some(x1) } else { None };
+    Some(x0).into_iter().chain(x1).filter(move |&u| u != y0 && u != y1)
+}
+
+/// Generalized partition crossover. The edges in only one of the routes split into connected components.
+/// A component crossed by exactly two shared edges is visited by both routes as a segment with the same ends,
+/// so the child takes the cheaper segment from either route. The other components all come from the same route,
+/// the one giving the cheaper child. The child is never worse than the best parent, and it takes linear time.
+pub fn gpx<W: Weight>(tsp: &SymmetricMatrix<W>, a: &Route<W>, b: &Route<W>) -> Route<W> {
+    let (pa, pb) = (&a.path, &b.path);
+    let size = pa.size();
+    assert_eq!(size, pb.size());
+    assert_eq!(size, tsp.size());
+
+    // Components of the graph of the edges in only one of the routes, by breadth first search.
+    let mut component = vec![usize::MAX; size];
+    let mut count = 0;
+    let mut queue = vec![];
+    for start in 0..size {
+        if component[start] != usize::MAX || only(pa, pb, start).next().is_none() { continue; }
+
+        component[start] = count;
+        queue.push(start);
+        while let Some(v) = queue.pop() {
+            for u in only(pa, pb, v).chain(only(pb, pa, v)) {
+                if component[u] == usize::MAX {
+                    component[u] = count;
+                    queue.push(u);
+                }
+            }
+        }
+        count += 1;
+    }
+
+    // Shared edges crossing each component, and the cost of each route inside it.
+    let mut crossings = vec![0; count];
+    let mut costs = vec![(0.0, 0.0); count];
+    for v in 0..size {
+        let c = component[v];
+        for u in only(pa, pb, v).filter(|&u| v < u) {
+            costs[c].0 += tsp[(v, u)].to_f64();
+        }
+        for u in only(pb, pa, v).filter(|&u| v < u) {
+            costs[c].1 += tsp[(v, u)].to_f64();
+        }
+
+        let (v0, v1) = pa[v];
+        for &u in [v0, v1].iter() {
+            if c != usize::MAX && component[u] != c {
+                crossings[c] += 1;
+            }
+        }
+    }
+
+    let child = |base: &Path| {
+        let path = Path::new((0..size).map(|v| match component[v] {
+            c if c != usize::MAX && crossings[c] == 2 => if costs[c].0 <= costs[c].1 { pa[v] } else { pb[v] },
+            _ => base[v],
+        }).collect());
+        debug_assert!(path.is_hamiltonian());
+        Route::new(tsp.cost(&path), path)
+    };
+
+    let (ca, cb) = (child(pa), child(pb));
+    if ca.cost <= cb.cost { ca } else { cb }
+}
+
+#[inline]
+fn key((v0, v1): (usize, usize)) -> (usize, usize) {
+    (v0.min(v1), v0.max(v1))
+}
+
+/// Route kept as an array of its vertices, walked forward or backward as `reversed` says.
+struct Walk {
+    order: Vec<usize>,
+    position: Vec<usize>,
+    reversed: bool,
+}
+
+impl Walk {
+    fn new(path: &Path) -> Self {
+        let order: Vec<_> = path.vertices_visited().collect();
+        let mut position = vec![0; order.len()];
+        for (i, &v) in order.iter().enumerate() {
+            position[v] = i;
+        }
+        Self { order, position, reversed: false }
+    }
+
+    fn step(&self, v: usize, forward: bool) -> usize {
+        let size = self.order.len();
+        if forward != self.reversed { self.order[(self.position[v] + 1) % size] } else { self.order[(self.position[v] + size - 1) % size] }
+    }
+
+    fn succ(&self, v: usize) -> usize {
+        self.step(v, true)
+    }
+
+    fn pred(&self, v: usize) -> usize {
+        self.step(v, false)
+    }
+
+    /// Reverses the walk from `a` to `b`. The array reverses the shorter side and the direction flips if it's the other one.
+    /// Returns what `undo` needs.
+    fn reverse(&mut self, a: usize, b: usize) -> (usize, usize, bool) {
+        let size = self.order.len();
+        let (i, j) = if self.reversed { (self.position[b], self.position[a]) } else { (self.position[a], self.position[b]) };
+        let (i, j, flip) = if (j + size - i) % size < size / 2 { (i, j, false) } else { ((j + 1) % size, (i + size - 1) % size, true) };
+
+        crate::reverse(&mut self.order, &mut self.position, i, j);
+        self.reversed ^= flip;
+        (i, j, flip)
+    }
+
+    fn undo(&mut self, (i, j, flip): (usize, usize, bool)) {
+        crate::reverse(&mut self.order, &mut self.position, i, j);
+        self.reversed ^= flip;
+    }
+}
+
+/// One Lin–Kernighan move from `t1`, adding only edges to the `neighbors` of each vertex.
+/// It removes the edge from `t1` to its successor `t2`, then goes on while the gain stays positive: it adds an edge
+/// from `t2` to a neighbor `t3`, removes the edge from `t3` to its predecessor `t4` by reversing the walk from `t2`
+/// to `t4`, and `t4` becomes the next `t2`. Edges removed are never added back, nor edges added removed.
+/// The move is cut back to the cheapest route it closed through an edge to a neighbor.
+/// Both directions are tried. Returns the vertices whose edges changed, if it improved.
+fn union_move<W: Weight>(tsp: &SymmetricMatrix<W>, neighbors: &[Vec<usize>], walk: &mut Walk, t1: usize) -> Option<Vec<usize>> {
+    let w = |a: usize, b: usize| tsp[(a, b)].to_f64();
+
+    // Neighbors to add an edge to from `t2`, with the most promising exchange first.
+    let choices = |walk: &Walk, t2: usize, gain: f64, added: &[(usize, usize)], removed: &[(usize, usize)]| {
+        let mut choices: Vec<_> = neighbors[t2].iter().copied()
+            .filter(|&t3| t3 != walk.succ(t2) && gain - w(t2, t3) > W::EPSILON.to_f64() && !removed.contains(&key((t2, t3))))
+            .map(|t3| (t3, walk.pred(t3)))
+            .filter(|&(t3, t4)| !added.contains(&key((t3, t4))) && !removed.contains(&key((t3, t4))))
+            .map(|(t3, t4)| (w(t3, t4) - w(t2, t3), t3))
+            .collect();
+        choices.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        choices.into_iter().map(|(_, t3)| t3).collect::<Vec<_>>()
+    };
+
+    for _ in 0..2 {
+        let t2 = walk.succ(t1);
+        let removed = [key((t1, t2))];
+
+        for first in choices(walk, t2, w(t1, t2), &[], &removed) {
+            let (mut t2, mut t3, mut gain) = (t2, first, w(t1, t2));
+            let (mut added, mut removed) = (vec![], removed.to_vec());
+            let mut touched = vec![t1, t2];
+            let mut undo = vec![];
+            let (mut best_gain, mut best_depth) = (W::EPSILON.to_f64(), 0);
+
+            loop {
+                let t4 = walk.pred(t3);
+                gain += w(t3, t4) - w(t2, t3);
+                added.push(key((t2, t3)));
+                removed.push(key((t3, t4)));
+                touched.extend_from_slice(&[t3, t4]);
+                undo.push(walk.reverse(t2, t4));
+
+                // Now `t4` follows `t1`, closing the route.
+                if neighbors[t4].contains(&t1) && gain - w(t4, t1) > best_gain {
+                    best_gain = gain - w(t4, t1);
+                    best_depth = undo.len();
+                }
+
+                t2 = t4;
+                match choices(walk, t2, gain, &added, &removed).first() {
+                    Some(&next) => t3 = next,
+                    None => break,
+                }
+            }
+
+            while undo.len() > best_depth {
+                walk.undo(undo.pop().unwrap());
+            }
+            if best_depth > 0 {
+                touched.truncate(2 * best_depth + 2);
+                return Some(touched);
+            }
+        }
+
+        walk.reversed = !walk.reversed;
+    }
+
+    None
+}
+
+/// Lin–Kernighan search adding only edges to the `neighbors` of each vertex. As in the fast local search,
+/// only moves from active vertices are tried: all of them at first, then the ones whose edges changed.
+fn union_lin_kernighan<W: Weight>(tsp: &SymmetricMatrix<W>, route: &Route<W>, neighbors: &[Vec<usize>]) -> Route<W> {
+    let size = tsp.size();
+    let mut walk = Walk::new(&route.path);
+    let mut active = vec![true; size];
+    let mut queue: VecDeque<_> = (0..size).collect();
+
+    while let Some(t1) = queue.pop_front() {
+        active[t1] = false;
+        if let Some(touched) = union_move(tsp, neighbors, &mut walk, t1) {
+            for v in touched {
+                if !active[v] {
+                    active[v] = true;
+                    queue.push_back(v);
+                }
+            }
+        }
+    }
+
+    let path = Path::from_order(&walk.order);
+    Route::new(tsp.cost(&path), path)
+}
+
+/// Merges routes, usually local minima from independent runs, into a tour that uses only the edges of their union.
+/// The routes are crossed by `gpx`, starting from the cheapest, and the child goes through a Lin–Kernighan search
+/// that only adds edges of the union, until neither improves. It's a heuristic: the result is never worse than
+/// the best route, but it may not be the cheapest tour of the union, nor a local minimum over all edges.
+pub fn tour_merge<W: Weight>(tsp: &SymmetricMatrix<W>, routes: &[Route<W>]) -> Route<W> {
+    assert!(!routes.is_empty());
+    let size = tsp.size();
+
+    let mut routes = routes.to_vec();
+    routes.sort_by(|a, b| a.cost.partial_cmp(&b.cost).unwrap());
+
+    let union: HashSet<_> = routes.iter().flat_map(|r| r.path.edges_visited().map(key)).collect();
+    let mut neighbors = vec![vec![]; size];
+    for &(v0, v1) in union.iter() {
+        neighbors[v0].push(v1);
+        neighbors[v1].push(v0);
+    }
+
+    let mut merged = routes[0].clone();
+    loop {
+        let before = merged.cost;
+        for route in routes.iter().skip(1) {
+            merged = gpx(tsp, &merged, route);
+        }
+        merged = union_lin_kernighan(tsp, &merged, &neighbors);
+        if !W::improves(before, merged.cost) { break; }
+    }
+
+    debug_assert!(merged.check_cost(tsp));
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::config::{GlsConfig, TieBreak};
+    use crate::fixture::scattered;
+    use crate::gpx::{gpx, tour_merge};
+    use crate::{gls_with_config, local_search};
+    use crate::matrix::SymmetricMatrix;
+    use crate::path::Path;
+    use crate::random::Random;
+    use crate::route::Route;
+
+    #[test]
+    fn partitions() {
+        // Around a circle, visiting the vertices in order is optimal.
+        let coords: Vec<_> = (0..12)
+            .map(|i| {
+                let angle = i as f64 * std::f64::consts::PI / 6.0;
+                ((1000.0 * angle.cos()) as i32, (1000.0 * angle.sin()) as i32)
+            })
+            .collect();
+        let tsp = SymmetricMatrix::<u32>::from_euc_2d(&coords);
+        let route = |order: &[usize]| {
+            let path = Path::from_order(order);
+            Route::new(tsp.cost(&path), path)
+        };
+
+        let optimal = route(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+        let a = route(&[0, 1, 3, 2, 4, 5, 6, 7, 8, 9, 10, 11]);
+        let b = route(&[0, 1, 2, 3, 4, 5, 6, 7, 9, 8, 10, 11]);
+
+        let child = gpx(&tsp, &a, &b);
+        assert_eq!(child, optimal);
+        assert_eq!(gpx(&tsp, &b, &a), optimal);
+        assert_eq!(gpx(&tsp, &a, &a), a);
+    }
+
+    #[test]
+    fn inheritance() {
+        let tsp = scattered::<u32>(40);
+        let candidates = tsp.candidates(8);
+        let mut random = Random::new(4);
+        let mut parent = || {
+            let mut route = tsp.randomized_neighbor(&candidates, &mut random);
+            local_search(&tsp, &mut route, &mut vec![]);
+            route
+        };
+
+        let key = |(v0, v1): (usize, usize)| (v0.min(v1), v0.max(v1));
+        let edges = |r: &Route| r.path.edges_visited().map(key).collect::<HashSet<_>>();
+        for _ in 0..5 {
+            let (a, b) = (parent(), parent());
+            let child = gpx(&tsp, &a, &b);
+            assert!(child.cost <= a.cost.min(b.cost));
+
+            // Every edge comes from a parent, and the ones in both are kept.
+            let (ea, eb, ec) = (edges(&a), edges(&b), edges(&child));
+            assert!(ea.intersection(&eb).all(|e| ec.contains(e)));
+            assert!(ec.iter().all(|e| ea.contains(e) || eb.contains(e)));
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let tsp = scattered::<f64>(60);
+
+        let routes: Vec<_> = (0..4)
+            .map(|seed| gls_with_config(&tsp, 10, &GlsConfig { tie_break: TieBreak::Random(seed), ..GlsConfig::default() }))
+            .collect();
+        let merged = tour_merge(&tsp, &routes);
+
+        assert!(merged.check_cost(&tsp));
+        assert!(routes.iter().all(|r| merged.cost <= r.cost));
+
+        let key = |(v0, v1): (usize, usize)| (v0.min(v1), v0.max(v1));
+        let union: HashSet<_> = routes.iter().flat_map(|r| r.path.edges_visited().map(key)).collect();
+        assert!(merged.path.edges_visited().all(|e| union.contains(&key(e))));
+    }
+
+    #[test]
+    fn union() {
+        let coords = [(85, 29), (58, 12), (34, 88), (38, 12), (57, 31), (55, 53), (90, 78), (27, 89), (46, 30), (11, 21)];
+        let tsp = SymmetricMatrix::<u32>::from_euc_2d(&coords);
+        let route = |order: &[usize]| {
+            let path = Path::from_order(order);
+            Route::new(tsp.cost(&path), path)
+        };
+
+        // Two 2-opt local minima, whose union holds a cheaper tour than crossing them finds.
+        let a = route(&[4, 5, 6, 2, 7, 9, 3, 8, 1, 0]);
+        let b = route(&[6, 2, 7, 9, 3, 1, 4, 8, 5, 0]);
+        assert_eq!((a.cost, b.cost), (324, 321));
+        assert_eq!(gpx(&tsp, &a, &b).cost, 321);
+        assert_eq!(gpx(&tsp, &b, &a).cost, 321);
+
+        // The cheapest tour of the union.
+        let merged = tour_merge(&tsp, &[a.clone(), b.clone()]);
+        assert_eq!(merged.cost, 317);
+        assert!(merged.check_cost(&tsp));
+
+        let key = |(v0, v1): (usize, usize)| (v0.min(v1), v0.max(v1));
+        let union: HashSet<_> = a.path.edges_visited().chain(b.path.edges_visited()).map(key).collect();
+        assert!(merged.path.edges_visited().all(|e| union.contains(&key(e))));
+    }
+}
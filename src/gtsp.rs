@@ -0,0 +1,357 @@
+use std::fs;
+use crate::coords::parse_coord;
+use crate::matrix::SymmetricMatrix;
+use crate::gls;
+
+/// Partition of the vertices in clusters. A generalized tour visits exactly one vertex of each cluster.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Clusters {
+    sets: Vec<Vec<usize>>,
+    cluster_of: Vec<usize>,
+}
+
+impl Clusters {
+    pub fn new(size: usize, sets: Vec<Vec<usize>>) -> Result<Self, String> {
+        let mut cluster_of = vec![usize::MAX; size];
+
+        for (c, set) in sets.iter().enumerate() {
+            if set.is_empty() {
+                return Err(format!("cluster {} is empty", c + 1));
+            }
+
+            for &v in set {
+                if v >= size {
+                    return Err(format!("vertex {} of cluster {} is out of range", v + 1, c + 1));
+                }
+                if cluster_of[v] != usize::MAX {
+                    return Err(format!("vertex {} is in clusters {} and {}", v + 1, cluster_of[v] + 1, c + 1));
+                }
+                cluster_of[v] = c;
+            }
+        }
+
+        if let Some(v) = cluster_of.iter().position(|&c| c == usize::MAX) {
+            return Err(format!("vertex {} is in no cluster", v + 1));
+        }
+
+        Ok(Self { sets, cluster_of })
+    }
+
+    pub fn len(&self) -> usize {
+        self.sets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+    }
+
+    pub fn cluster_of(&self, vertex: usize) -> usize {
+        self.cluster_of[vertex]
+    }
+
+    pub fn set(&self, cluster: usize) -> &[usize] {
+        &self.sets[cluster]
+    }
+}
+
+/// Generalized tour: the representatives of each cluster in the order they are visited.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct GtspRoute {
+    pub cost: u32,
+    pub tour: Vec<usize>,
+}
+
+impl GtspRoute {
+    /// Check if the tour visits exactly one vertex of each cluster.
+    pub fn is_valid(&self, clusters: &Clusters) -> bool {
+        let mut visited = vec![false; clusters.len()];
+
+        for &v in self.tour.iter() {
+            let c = clusters.cluster_of(v);
+            if visited[c] { return false; }
+            visited[c] = true;
+        }
+
+        visited.into_iter().all(|v| v)
+    }
+}
+
+fn tour_cost(tsp: &SymmetricMatrix, tour: &[usize]) -> u32 {
+    let closing = (tour[tour.len() - 1], tour[0]);
+    tour.windows(2)
+        .map(|e| tsp[(e[0], e[1])])
+        .sum::<u32>() + tsp[closing]
+}
+
+/// Parses a TSPLIB GTSP file with EUC_2D coordinates and a `GTSP_SET_SECTION`.
+pub fn parse(text: &str) -> Result<(SymmetricMatrix, Clusters), String> {
+    enum Section { Header, Coords, Sets }
+
+    let mut section = Section::Header;
+    let mut dimension = None;
+    let mut coords = vec![];
+    let mut sets: Vec<Vec<usize>> = vec![];
+    let mut current: Option<Vec<usize>> = None;
+
+    for (line_number, line) in text.lines().enumerate() {
+        let line_number = line_number + 1;
+        let line = line.trim();
+        let err = |msg: &str| format!("line {}: {}", line_number, msg);
+
+        if line.is_empty() { continue; }
+        if line == "EOF" { break; }
+
+        if line.starts_with("NODE_COORD_SECTION") {
+            section = Section::Coords;
+            continue;
+        }
+        if line.starts_with("GTSP_SET_SECTION") {
+            section = Section::Sets;
+            continue;
+        }
+
+        match section {
+            Section::Header => {
+                let mut kv = line.splitn(2, ':');
+                let key = kv.next().unwrap().trim();
+                let value = kv.next().ok_or_else(|| err("expected `KEY : VALUE`"))?.trim();
+
+                match key {
+                    "TYPE" if value != "GTSP" => return Err(err(&format!("unsupported type {}", value))),
+                    "EDGE_WEIGHT_TYPE" if value != "EUC_2D" => return Err(err(&format!("unsupported edge weight {}", value))),
+                    "DIMENSION" => dimension = Some(value.parse::<usize>().map_err(|e| err(&e.to_string()))?),
+                    _ => {}
+                }
+            }
+
+            Section::Coords => {
+                let values: Vec<_> = line.split_whitespace().collect();
+                if values.len() != 3 {
+                    return Err(err("expected `id x y`"));
+                }
+
+                values[0].parse::<f64>().map_err(|e| err(&e.to_string()))?;
+                coords.push((parse_coord(values[1]).map_err(|e| err(&e))?, parse_coord(values[2]).map_err(|e| err(&e))?));
+            }
+
+            Section::Sets => {
+                for token in line.split_whitespace() {
+                    let value = token.parse::<i64>().map_err(|e| err(&e.to_string()))?;
+
+                    match (&mut current, value) {
+                        // The first number of a set is its id.
+                        (None, _) => current = Some(vec![]),
+                        (Some(_), -1) => sets.push(current.take().unwrap()),
+                        (Some(set), v) if v > 0 => set.push(v as usize - 1),
+                        (Some(_), v) => return Err(err(&format!("invalid vertex {}", v))),
+                    }
+                }
+            }
+        }
+    }
+
+    if current.is_some() {
+        return Err("last set is not terminated by -1".to_owned());
+    }
+    if let Some(dimension) = dimension {
+        if dimension != coords.len() {
+            return Err(format!("expected {} coordinates, found {}", dimension, coords.len()));
+        }
+    }
+
+    let clusters = Clusters::new(coords.len(), sets)?;
+    let tsp = SymmetricMatrix::try_from_euc_2d(&coords).map_err(|e| e.to_string())?;
+    Ok((tsp, clusters))
+}
+
+pub fn from_file(file: &str) -> Result<(SymmetricMatrix, Clusters), String> {
+    let text = fs::read_to_string(file).map_err(|e| e.to_string())?;
+    parse(&text)
+}
+
+/// Picks the best representative of each cluster for a fixed cluster order.
+/// Shortest path on the layered graph of the clusters starting from every vertex of the smallest one.
+/// Costs saturate instead of overflowing.
+pub fn cluster_optimization(tsp: &SymmetricMatrix, clusters: &Clusters, order: &[usize]) -> GtspRoute {
+    // Rotate the order so it starts on the smallest cluster.
+    let first = (0..order.len()).min_by_key(|&i| clusters.set(order[i]).len()).unwrap();
+    let order: Vec<_> = order[first..].iter().chain(order[..first].iter()).copied().collect();
+
+    let mut best = GtspRoute { cost: u32::MAX, tour: vec![] };
+    let mut dist: Vec<Vec<Option<u32>>> = order.iter().map(|&c| vec![None; clusters.set(c).len()]).collect();
+    let mut pred: Vec<Vec<usize>> = order.iter().map(|&c| vec![0; clusters.set(c).len()]).collect();
+
+    for (s, &start) in clusters.set(order[0]).iter().enumerate() {
+        for d in dist[0].iter_mut() { *d = None; }
+        dist[0][s] = Some(0);
+
+        for layer in 1..order.len() {
+            let (done, todo) = dist.split_at_mut(layer);
+            let (prev_dist, cur_dist) = (&done[layer - 1], &mut todo[0]);
+            let prev_set = clusters.set(order[layer - 1]);
+
+            for (k, &v) in clusters.set(order[layer]).iter().enumerate() {
+                let (p, d) = prev_set.iter().copied()
+                    .zip(prev_dist.iter().copied())
+                    .enumerate()
+                    .filter_map(|(p, (u, d))| Some((p, d?.saturating_add(tsp[(u, v)]))))
+                    .min_by_key(|&(_, d)| d)
+                    .unwrap();
+
+                cur_dist[k] = Some(d);
+                pred[layer][k] = p;
+            }
+        }
+
+        let last = order.len() - 1;
+        let (k, cost) = clusters.set(order[last]).iter().copied()
+            .zip(dist[last].iter().copied())
+            .enumerate()
+            .filter_map(|(k, (v, d))| Some((k, d?.saturating_add(tsp[(v, start)]))))
+            .min_by_key(|&(_, c)| c)
+            .unwrap();
+
+        if best.tour.is_empty() || cost < best.cost {
+            let mut tour = vec![0; order.len()];
+            let mut k = k;
+            for layer in (0..order.len()).rev() {
+                tour[layer] = clusters.set(order[layer])[k];
+                k = pred[layer][k];
+            }
+            best = GtspRoute { cost, tour };
+        }
+    }
+
+    best
+}
+
+/// Solves the generalized TSP alternating `gls` over the current representatives and the cluster optimization.
+pub fn gtsp(tsp: &SymmetricMatrix, clusters: &Clusters, steps: usize) -> GtspRoute {
+    let size = clusters.len();
+    assert!(size > 0);
+
+    // Start with the first vertex of each cluster.
+    let mut representatives: Vec<_> = (0..size).map(|c| clusters.set(c)[0]).collect();
+    let mut best = GtspRoute { cost: u32::MAX, tour: vec![] };
+
+    loop {
+        // Any order of up to three clusters is the same cycle.
+        let order: Vec<_> = if size <= 3 {
+            (0..size).collect()
+        } else {
+            let mut reduced = SymmetricMatrix::from_size(size);
+            for i in 0..size {
+                for j in i + 1..size {
+                    reduced.set((i, j), tsp[(representatives[i], representatives[j])]);
+                }
+            }
+            gls(&reduced, steps).path.vertices_visited().collect()
+        };
+
+        let candidate = cluster_optimization(tsp, clusters, &order);
+        if candidate.cost >= best.cost { break; }
+
+        for &v in candidate.tour.iter() {
+            representatives[clusters.cluster_of(v)] = v;
+        }
+        best = candidate;
+    }
+
+    debug_assert!(best.is_valid(clusters));
+    debug_assert_eq!(best.cost, tour_cost(tsp, &best.tour));
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gtsp::{parse, gtsp, cluster_optimization, tour_cost, Clusters};
+    use crate::matrix::SymmetricMatrix;
+
+    const INSTANCE: &str = "\
+NAME : 5test
+TYPE : GTSP
+DIMENSION : 10
+GTSP_SETS : 5
+EDGE_WEIGHT_TYPE : EUC_2D
+NODE_COORD_SECTION
+1 0 0
+2 100 100
+3 10 0
+4 100 0
+5 20 10
+6 -50 -50
+7 10 20
+8 0 90
+9 0 20
+10 60 60
+GTSP_SET_SECTION:
+1 1 2 -1
+2 3 4 -1
+3 5 6
+-1
+4 7 8 -1
+5 9 10 -1
+EOF
+";
+
+    #[test]
+    fn parse_sets() {
+        let (tsp, clusters) = parse(INSTANCE).unwrap();
+
+        assert_eq!(tsp.size(), 10);
+        assert_eq!(clusters.len(), 5);
+        assert_eq!(clusters.set(2), &[4, 5]);
+        assert_eq!(clusters.cluster_of(9), 4);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse("TYPE : TSP\n").err().unwrap(), "line 1: unsupported type TSP");
+
+        let missing = INSTANCE.replace("5 9 10 -1", "5 9 -1");
+        assert_eq!(parse(&missing).err().unwrap(), "vertex 10 is in no cluster");
+
+        let far = INSTANCE.replace("2 100 100", "2 3e10 100");
+        assert_eq!(parse(&far).err().unwrap(), "line 8: coordinate 3e10 is out of range");
+
+        let overflowing = INSTANCE.replace("2 100 100", "2 2000000000 2000000000").replace("6 -50 -50", "6 -2000000000 -2000000000");
+        assert!(parse(&overflowing).is_err());
+    }
+
+    #[test]
+    fn saturation() {
+        let mut tsp = SymmetricMatrix::from_size(4);
+        tsp.set((0, 1), u32::MAX - 1);
+        tsp.set((1, 2), 10);
+        tsp.set((1, 3), 1);
+        tsp.set((0, 2), 1);
+        tsp.set((0, 3), 1);
+        let clusters = Clusters::new(4, vec![vec![0], vec![1], vec![2, 3]]).unwrap();
+
+        let route = cluster_optimization(&tsp, &clusters, &[0, 1, 2]);
+        assert!(route.is_valid(&clusters));
+        assert_eq!(route.cost, u32::MAX);
+    }
+
+    #[test]
+    fn optimization() {
+        let (tsp, clusters) = parse(INSTANCE).unwrap();
+        let route = cluster_optimization(&tsp, &clusters, &[0, 1, 2, 3, 4]);
+
+        // The vertices close to the origin.
+        let mut tour = route.tour.clone();
+        tour.sort();
+        assert_eq!(tour, vec![0, 2, 4, 6, 8]);
+        assert_eq!(route.cost, tour_cost(&tsp, &route.tour));
+    }
+
+    #[test]
+    fn solve() {
+        let (tsp, clusters) = parse(INSTANCE).unwrap();
+        let route = gtsp(&tsp, &clusters, 10);
+
+        assert!(route.is_valid(&clusters));
+        assert_eq!(route.cost, tour_cost(&tsp, &route.tour));
+        assert_eq!(route.cost, 68);
+    }
+}
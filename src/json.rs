@@ -0,0 +1,165 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::matrix::SymmetricMatrix;
+use crate::route::Route;
+use crate::weight::Weight;
+
+/// Problem as exchanged with other services, either as points or as explicit weights.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Problem<W = u32> {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(flatten)]
+    pub weights: Weights<W>,
+}
+
+/// Either `"coordinates": [[x, y], ...]` or `"matrix": [[w, ...], ...]`.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Weights<W> {
+    Coordinates(Vec<(i32, i32)>),
+    Matrix(Vec<Vec<W>>),
+}
+
+impl<W: Weight> Problem<W> {
+    /// Distances of the problem. An explicit matrix must be square and symmetric.
+    pub fn to_matrix(&self) -> Result<SymmetricMatrix<W>, String> {
+        match &self.weights {
+            Weights::Coordinates(coords) if coords.len() < 2 => Err(format!("expected at least 2 vertices, found {}", coords.len())),
+            Weights::Coordinates(coords) => SymmetricMatrix::try_from_euc_2d(coords).map_err(|e| e.to_string()),
+            Weights::Matrix(rows) => {
+                let size = rows.len();
+                if size < 2 {
+                    return Err(format!("expected at least 2 vertices, found {}", size));
+                }
+
+                let mut res = SymmetricMatrix::from_size(size);
+                for (i, row) in rows.iter().enumerate() {
+                    if row.len() != size {
+                        return Err(format!("row {} has {} weights, expected {}", i, row.len(), size));
+                    }
+
+                    for j in i + 1..size {
+                        if row[j] != rows[j][i] {
+                            return Err(format!("weights of ({}, {}) and ({}, {}) differ", i, j, j, i));
+                        }
+                        res.set((i, j), row[j]);
+                    }
+                }
+
+                Ok(res)
+            }
+        }
+    }
+}
+
+/// Solution as reported to other services.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Solution<W = u32> {
+    pub cost: W,
+    /// Vertices in the order they are visited, starting from 0.
+    pub tour: Vec<usize>,
+    /// In seconds.
+    pub runtime: f64,
+    pub algorithm: String,
+    pub parameters: Map<String, Value>,
+}
+
+impl<W: Weight> Solution<W> {
+    pub fn new(route: &Route<W>, runtime: Duration, algorithm: &str) -> Self {
+        let tour = Some(0).into_iter()
+            .chain(route.path.vertices_visited().take_while(|&v| v != 0))
+            .collect();
+
+        Self { cost: route.cost, tour, runtime: runtime.as_secs_f64(), algorithm: algorithm.to_owned(), parameters: Map::new() }
+    }
+
+    /// Records a parameter the algorithm ran with.
+    pub fn with_parameter(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.parameters.insert(name.to_owned(), value.into());
+        self
+    }
+}
+
+/// Errors have the line and column of the document they happened on.
+pub fn read_problem<W: Weight + for<'de> Deserialize<'de>, R: Read>(reader: R) -> Result<Problem<W>, String> {
+    serde_json::from_reader(reader).map_err(|e| e.to_string())
+}
+
+pub fn problem_from_file<W: Weight + for<'de> Deserialize<'de>>(file: &str) -> Result<Problem<W>, String> {
+    let reader = File::open(file).map_err(|e| format!("{}: {}", file, e))?;
+    read_problem(BufReader::new(reader))
+}
+
+pub fn write_solution<W: Weight + Serialize, T: Write>(writer: T, solution: &Solution<W>) -> Result<(), String> {
+    serde_json::to_writer_pretty(writer, solution).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::json::{read_problem, write_solution, Problem, Solution};
+    use crate::matrix::SymmetricMatrix;
+    use crate::path::Path;
+    use crate::route::Route;
+
+    #[test]
+    fn coordinates() {
+        let text = r#"{ "name": "square", "coordinates": [[0, 0], [10, 0], [10, 10], [0, 10]] }"#;
+        let problem: Problem = read_problem(text.as_bytes()).unwrap();
+        assert_eq!(problem.name, "square");
+
+        let tsp = problem.to_matrix().unwrap();
+        assert_eq!(tsp[(0, 2)], 14);
+    }
+
+    #[test]
+    fn matrix() {
+        let text = r#"{ "matrix": [[0, 1.5, 2], [1.5, 0, 3], [2, 3, 0]] }"#;
+        let problem: Problem<f64> = read_problem(text.as_bytes()).unwrap();
+        assert_eq!(problem.to_matrix().unwrap()[(1, 0)], 1.5);
+
+        let asymmetric = text.replacen("1.5", "1", 1);
+        let problem: Problem<f64> = read_problem(asymmetric.as_bytes()).unwrap();
+        assert_eq!(problem.to_matrix().err().unwrap(), "weights of (0, 1) and (1, 0) differ");
+
+        let err = read_problem::<u32, _>("{ \"matrix\":\n [[0, -1]] }".as_bytes()).err().unwrap();
+        assert!(err.starts_with("invalid value: integer `-1`") && err.contains("line 2"), "{}", err);
+    }
+
+    #[test]
+    fn serde_derives() {
+        let tsp = SymmetricMatrix::<u32>::from_euc_2d(&[(0, 0), (3, 4), (6, 0)]);
+        let json = serde_json::to_string(&tsp).unwrap();
+        assert!(serde_json::from_str::<SymmetricMatrix>(&json).unwrap() == tsp);
+        assert!(serde_json::from_str::<SymmetricMatrix>(r#"{ "size": 2, "data": [0, 1, 2, 0] }"#).is_err());
+
+        let route = Route::new(16, Path::from_order(&[0, 1, 2]));
+        let json = serde_json::to_string(&route).unwrap();
+        assert_eq!(json, r#"{"cost":16,"path":[[1,2],[0,2],[0,1]]}"#);
+        assert_eq!(serde_json::from_str::<Route>(&json).unwrap(), route);
+
+        // The path is checked before the route can be used.
+        let broken = r#"{"cost":16,"path":[[1,2],[0,7],[0,1]]}"#;
+        assert!(serde_json::from_str::<Route>(broken).unwrap_err().to_string().contains("out of range"));
+    }
+
+    #[test]
+    fn solution() {
+        let route = Route::new(16, Path::from_order(&[0, 2, 1]));
+        let solution = Solution::new(&route, Duration::from_millis(1500), "gls").with_parameter("steps", 10);
+        assert_eq!(solution.tour.len(), 3);
+        assert_eq!(solution.tour[0], 0);
+
+        let mut out = vec![];
+        write_solution(&mut out, &solution).unwrap();
+        let read: Solution = serde_json::from_slice(&out).unwrap();
+        assert_eq!(read, solution);
+        assert_eq!(read.parameters["steps"], 10);
+    }
+}
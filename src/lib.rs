@@ -0,0 +1,418 @@
+use std::collections::VecDeque;
+use rayon::prelude::*;
+use tsplib::Tsp;
+use crate::matrix::{EdgeWeights, SymmetricMatrix};
+use crate::route::Route;
+use crate::checkpoint::GlsState;
+use crate::config::{Decay, GlsConfig};
+use crate::penalty::Augmented;
+use crate::trace::{Event, Observer, Trace};
+use crate::weight::Weight;
+
+pub mod aco;
+pub mod anneal;
+pub mod checkpoint;
+pub mod config;
+pub mod coords;
+pub mod eax;
+#[cfg(test)]
+mod fixture;
+pub mod gpx;
+pub mod gtsp;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod matrix;
+pub mod mtsp;
+pub mod multistart;
+pub mod path;
+pub mod penalty;
+pub mod prize;
+mod random;
+pub mod render;
+pub mod route;
+pub mod sop;
+pub mod tabu;
+pub mod trace;
+pub mod tsptw;
+pub mod validate;
+pub mod weight;
+
+/// Whether twisting the edges decreases the cost over the aspiration matrix by more than its threshold.
+#[inline]
+fn aspires<W: Weight>(aspiration: Option<(&SymmetricMatrix<W>, W)>, (a0, a1): (usize, usize), (b0, b1): (usize, usize)) -> bool {
+    match aspiration {
+        Some((cost, threshold)) => {
+            let cost = |e: (usize, usize)| unsafe { cost.get_unchecked(e) };
+            W::improves(cost((a0, a1)) + cost((b0, b1)), cost((a0, b0)) + cost((a1, b1)) + threshold)
+        }
+        None => false,
+    }
+}
+
+/// The vertices of the candidate edges must be in range for the matrix, as the ones from a path of the same size.
+/// With `aspiration`, moves that decrease the cost over its matrix by more than its threshold are also taken.
+fn local_search_step<W: Weight, T: EdgeWeights<W>>(tsp: &T, candidate_edges: &[(usize, usize)], aspiration: Option<(&SymmetricMatrix<W>, W)>) -> Option<((usize, usize), (usize, usize))> {
+    debug_assert!(candidate_edges.iter().all(|&(v0, v1)| v0 < tsp.size() && v1 < tsp.size()));
+
+    // The edge costs are read without bound checks as this is the hot loop of the search.
+    let tsp = |e: (usize, usize)| unsafe { tsp.weight_unchecked(e) };
+
+    // Taking the first improving move instead of any of them makes the search deterministic, so it can be resumed.
+    candidate_edges
+        .par_iter()
+        .copied()
+        .enumerate()
+        .find_map_first(|(i, (a0, a1))| {
+            let initial_cost = tsp((a0, a1));
+
+            let neighbors = candidate_edges
+                .iter()
+                .copied()
+                .skip(i + 2);
+
+            for (b0, b1) in neighbors {
+                let cost_decrease = initial_cost + tsp((b0, b1));
+                let cost_increase = tsp((a0, b0)) + tsp((a1, b1));
+
+                if W::improves(cost_decrease, cost_increase) || aspires(aspiration, (a0, a1), (b0, b1)) {
+                    return Some(((a0, a1), (b0, b1)));
+                }
+            }
+
+            None
+        })
+}
+
+pub fn local_search<W: Weight>(tsp: &SymmetricMatrix<W>, candidate: &mut Route<W>, edge_buffer: &mut Vec<(usize, usize)>) {
+    local_search_with_cost(tsp, tsp, candidate, edge_buffer)
+}
+
+/// Local search guided by `tsp` while the cost of the candidate is kept over `cost`.
+pub fn local_search_with_cost<W: Weight, T: EdgeWeights<W>>(tsp: &T, cost: &SymmetricMatrix<W>, candidate: &mut Route<W>, edge_buffer: &mut Vec<(usize, usize)>) {
+    local_search_observed(tsp, cost, candidate, edge_buffer, &mut ())
+}
+
+fn local_search_observed<W: Weight, T: EdgeWeights<W>, O: Observer>(tsp: &T, cost: &SymmetricMatrix<W>, candidate: &mut Route<W>, edge_buffer: &mut Vec<(usize, usize)>, observer: &mut O) {
+    assert_eq!(candidate.path.size(), tsp.size());
+    assert_eq!(candidate.path.size(), cost.size());
+
+    while let Some((a, b)) = {
+        candidate.path.edges_visited_buffered(edge_buffer);
+        local_search_step(tsp, edge_buffer, None)
+    } {
+        observer.twist(a, b);
+        candidate.twist(cost, a, b);
+    }
+}
+
+pub fn load_problem() -> SymmetricMatrix {
+    let home = env!("CARGO_MANIFEST_DIR").to_owned();
+    let tsp = Tsp::from_file(&(home + "/data/pcb3038.tsp")).unwrap();
+    SymmetricMatrix::from_tsplib(&tsp)
+}
+
+pub fn gls<W: Weight>(tsp: &SymmetricMatrix<W>, steps: usize) -> Route<W> {
+    gls_with_config(tsp, steps, &GlsConfig::default())
+}
+
+pub fn gls_with_config<W: Weight>(tsp: &SymmetricMatrix<W>, steps: usize, config: &GlsConfig) -> Route<W> {
+    gls_observed(tsp, steps, config, &mut ())
+}
+
+/// Same as `gls_with_config`, reporting every move and penalty update to the observer.
+pub fn gls_observed<W: Weight, O: Observer>(tsp: &SymmetricMatrix<W>, steps: usize, config: &GlsConfig, observer: &mut O) -> Route<W> {
+    let state = GlsState::start(tsp, config, observer);
+    gls_run(tsp, state, steps, config, observer, 0, |_| {})
+}
+
+/// Same as `gls_with_config`, passing the state of the search to `checkpoint` after every `every` steps.
+pub fn gls_with_checkpoints<W: Weight>(tsp: &SymmetricMatrix<W>, steps: usize, config: &GlsConfig, every: usize, checkpoint: impl FnMut(&GlsState<W>)) -> Route<W> {
+    gls_resume(tsp, GlsState::new(tsp, config), steps, config, every, checkpoint)
+}
+
+/// Continues a search from a checkpoint until `steps` steps are done in total.
+/// The result is the same as the one of a search that wasn't interrupted, as long as the config is the same.
+pub fn gls_resume<W: Weight>(tsp: &SymmetricMatrix<W>, state: GlsState<W>, steps: usize, config: &GlsConfig, every: usize, checkpoint: impl FnMut(&GlsState<W>)) -> Route<W> {
+    gls_run(tsp, state, steps, config, &mut (), every, checkpoint)
+}
+
+/// Local search over the augmented weights that keeps track of the best route found on the way.
+/// Every pair of edges of the route is tried until none improves.
+/// With aspiration, it also takes moves that lead to a route cheaper than the best one.
+fn gls_local_search<W: Weight, O: Observer>(tsp: &SymmetricMatrix<W>, state: &mut GlsState<W>, aspiration: bool, edge_buffer: &mut Vec<(usize, usize)>, observer: &mut O) {
+    let tsp_with_penalties = Augmented::new(tsp, &state.penalties, state.penalty_factor);
+
+    while let Some((a, b)) = {
+        state.route.path.edges_visited_buffered(edge_buffer);
+        // The route is never cheaper than the best one, so this doesn't underflow.
+        let aspiration = if aspiration { Some((tsp, state.route.cost - state.best.cost)) } else { None };
+        local_search_step(&tsp_with_penalties, edge_buffer, aspiration)
+    } {
+        observer.twist(a, b);
+        state.route.twist(tsp, a, b);
+
+        if state.route.cost < state.best.cost {
+            state.best = state.route.clone();
+            observer.best();
+        }
+    }
+}
+
+/// First improving move that links the vertex to one of its candidates, removing one of the two edges of the path
+/// that touch the vertex. The edges are oriented as in `order`, and `position` has the index of each vertex in it.
+fn fast_local_search_step<W: Weight, T: EdgeWeights<W>>(tsp: &T, order: &[usize], position: &[usize], candidates: &[usize], vertex: usize, aspiration: Option<(&SymmetricMatrix<W>, W)>) -> Option<((usize, usize), (usize, usize))> {
+    let size = order.len();
+    let tsp = |e: (usize, usize)| unsafe { tsp.weight_unchecked(e) };
+    let succ = |v: usize| order[(position[v] + 1) % size];
+    let pred = |v: usize| order[(position[v] + size - 1) % size];
+    let improves = |(a0, a1), (b0, b1)| {
+        W::improves(tsp((a0, a1)) + tsp((b0, b1)), tsp((a0, b0)) + tsp((a1, b1))) || aspires(aspiration, (a0, a1), (b0, b1))
+    };
+
+    let (vs, vp) = (succ(vertex), pred(vertex));
+    candidates.iter().find_map(|&c| {
+        // Edges that share a vertex can't be twisted.
+        if c == vs || c == vp { return None; }
+
+        let (after, before) = (((vertex, vs), (c, succ(c))), ((vp, vertex), (pred(c), c)));
+        [after, before].iter().copied().find(|&(a, b)| improves(a, b))
+    })
+}
+
+/// Reverses the vertices of `order` from the index `i` to `j`, wrapping around, keeping `position` up to date.
+fn reverse(order: &mut [usize], position: &mut [usize], i: usize, j: usize) {
+    let size = order.len();
+    let (mut i, mut j) = (i, j);
+    let length = (j + size - i) % size + 1;
+    for _ in 0..length / 2 {
+        order.swap(i, j);
+        position[order[i]] = i;
+        position[order[j]] = j;
+        i = (i + 1) % size;
+        j = (j + size - 1) % size;
+    }
+}
+
+/// Guided fast local search: each vertex has an activation bit, set for the endpoints of the edges whose penalties changed
+/// and for the endpoints of the edges changed by each move. Only moves from active vertices to their candidates are tried,
+/// and a vertex is deactivated once none of them improves.
+/// The route is kept as an array besides the path, and each move reverses the shorter of the two segments it splits.
+#[allow(clippy::too_many_arguments)]
+fn gls_fast_local_search<W: Weight, O: Observer>(tsp: &SymmetricMatrix<W>, state: &mut GlsState<W>, aspiration: bool, candidates: &[Vec<usize>], changed: &[usize], edge_buffer: &mut Vec<(usize, usize)>, observer: &mut O) {
+    let size = tsp.size();
+    let tsp_with_penalties = Augmented::new(tsp, &state.penalties, state.penalty_factor);
+
+    let mut active = vec![false; size];
+    let mut queue = VecDeque::new();
+    let activate = |v: usize, active: &mut [bool], queue: &mut VecDeque<usize>| if !active[v] {
+        active[v] = true;
+        queue.push_back(v);
+    };
+    for &v in changed {
+        activate(v, &mut active, &mut queue);
+    }
+
+    state.route.path.edges_visited_buffered(edge_buffer);
+    let mut order: Vec<_> = edge_buffer.iter().map(|&(v0, _)| v0).collect();
+    let mut position = vec![0; size];
+    for (i, &v) in order.iter().enumerate() {
+        position[v] = i;
+    }
+
+    while let Some(v) = queue.pop_front() {
+        active[v] = false;
+
+        // The route is never cheaper than the best one, so this doesn't underflow.
+        let threshold = if aspiration { Some((tsp, state.route.cost - state.best.cost)) } else { None };
+        if let Some((a, b)) = fast_local_search_step(&tsp_with_penalties, &order, &position, &candidates[v], v, threshold) {
+            observer.twist(a, b);
+            state.route.twist(tsp, a, b);
+            for &u in [a.0, a.1, b.0, b.1].iter() {
+                activate(u, &mut active, &mut queue);
+            }
+
+            // Either a1 to b0 or b1 to a0 is reversed, whichever is shorter.
+            let (i, j) = (position[a.1], position[b.0]);
+            if (j + size - i) % size < size / 2 {
+                reverse(&mut order, &mut position, i, j);
+            } else {
+                reverse(&mut order, &mut position, (j + 1) % size, (i + size - 1) % size);
+            }
+
+            if state.route.cost < state.best.cost {
+                state.best = state.route.clone();
+                observer.best();
+            }
+        }
+    }
+
+    state.route.path.edges_visited_buffered(edge_buffer);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn gls_run<W: Weight, O: Observer>(tsp: &SymmetricMatrix<W>, mut state: GlsState<W>, steps: usize, config: &GlsConfig, observer: &mut O, every: usize, checkpoint: impl FnMut(&GlsState<W>)) -> Route<W> {
+    gls_steps(tsp, &mut state, steps, config, observer, every, checkpoint);
+    gls_finish(tsp, state, observer)
+}
+
+/// Runs the search until `steps` steps are done in total.
+#[allow(clippy::too_many_arguments)]
+fn gls_steps<W: Weight, O: Observer>(tsp: &SymmetricMatrix<W>, state: &mut GlsState<W>, steps: usize, config: &GlsConfig, observer: &mut O, every: usize, mut checkpoint: impl FnMut(&GlsState<W>)) {
+    let size = tsp.size();
+    assert_eq!(state.route.path.size(), size);
+    assert_eq!(state.best.path.size(), size);
+    assert_eq!(state.penalties.size(), size);
+
+    let mut edge_buffer = Vec::with_capacity(size);
+    let mut utilities = Vec::with_capacity(size);
+    state.route.path.edges_visited_buffered(&mut edge_buffer);
+    let candidates = if config.fast { tsp.candidates(config.candidates) } else { vec![] };
+
+    while state.step < steps {
+        // Vertices around which the penalties changed, the only ones where the fast local search looks for moves.
+        let mut changed = vec![];
+
+        // Decaying before the step instead of after the previous one keeps the checkpoints free of pending activations.
+        if state.step > 0 && config.decays_after(state.step) {
+            let factor = match config.decay {
+                Decay::Scale { factor, .. } => factor,
+                _ => 0.0,
+            };
+            for ((v0, v1), penalty) in state.penalties.penalized() {
+                observer.penalty((v0, v1), (penalty as f64 * factor) as u32);
+                changed.extend_from_slice(&[v0, v1]);
+            }
+            state.penalties.scale(factor);
+        }
+
+        // The edge buffer will have the correct edges because the last iteration of the local search doesn't change the path.
+        utilities.clear();
+        utilities.extend(edge_buffer.iter().map(|&e| config.utility.eval(tsp[e].to_f64(), state.penalties.get(e))));
+
+        for (v0, v1) in config.choose(&edge_buffer, &utilities, state.step) {
+            let penalty = state.penalties.inc((v0, v1), config.increment);
+            observer.penalty((v0, v1), penalty);
+            changed.extend_from_slice(&[v0, v1]);
+        }
+
+        if config.fast {
+            gls_fast_local_search(tsp, state, config.aspiration, &candidates, &changed, &mut edge_buffer, observer);
+        } else {
+            gls_local_search(tsp, state, config.aspiration, &mut edge_buffer, observer);
+        }
+        state.step += 1;
+        observer.step();
+
+        if every > 0 && state.step.is_multiple_of(every) {
+            checkpoint(state);
+        }
+    }
+}
+
+/// The cheaper of the current and the best routes, each brought to a local minimum without penalties.
+fn gls_finish<W: Weight, O: Observer>(tsp: &SymmetricMatrix<W>, state: GlsState<W>, observer: &mut O) -> Route<W> {
+    let mut edge_buffer = Vec::with_capacity(tsp.size());
+
+    // Guarantee it's at least on a local minimum
+    let mut route = state.route;
+    local_search_observed(tsp, tsp, &mut route, &mut edge_buffer, observer);
+
+    // The observer has seen the best route as it was found, so it's sent back there and then through the moves.
+    let mut best = state.best;
+    let mut moves = Trace::default();
+    local_search_observed(tsp, tsp, &mut best, &mut edge_buffer, &mut moves);
+    if best.cost < route.cost {
+        observer.restore();
+        for event in moves.events {
+            if let Event::Twist(a, b) = event { observer.twist(a, b); }
+        }
+        route = best;
+    }
+
+    assert!(route.path.is_hamiltonian());
+    debug_assert!(route.check_cost(tsp));
+
+    route
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fast_local_search_step, gls, gls_fast_local_search, gls_with_config};
+    use crate::checkpoint::GlsState;
+    use crate::config::{Decay, GlsConfig, TieBreak, Utility};
+    use crate::fixture::scattered;
+    use crate::matrix::SymmetricMatrix;
+    use crate::path::Path;
+    use crate::penalty::Penalties;
+    use crate::route::Route;
+    use crate::weight::Weight;
+
+    #[test]
+    fn real_weights() {
+        let coords = [(0, 0), (3, 0), (3, 3), (0, 3), (1, 1)];
+
+        // Truncated, the detour to the inner vertex costs nothing.
+        let tsp = SymmetricMatrix::<u32>::from_euc_2d(&coords);
+        assert_eq!(gls(&tsp, 10).cost, 12);
+
+        let tsp = SymmetricMatrix::<f64>::from_euc_2d(&coords);
+        let route = gls(&tsp, 10);
+        assert!(route.cost.approx_eq(9.0 + 2f64.sqrt() + 5f64.sqrt()));
+        assert!(route.check_cost(&tsp));
+    }
+
+    #[test]
+    fn fast_local_search() {
+        let tsp = scattered::<u32>(40);
+        let candidates = tsp.candidates(5);
+
+        // A bad route, with every vertex active and no penalties.
+        let order: Vec<_> = (0..40).map(|i| i * 7 % 40).collect();
+        let path = Path::from_order(&order);
+        let route = Route::new(tsp.cost(&path), path);
+        let mut state = GlsState { route: route.clone(), best: route, penalties: Penalties::new(40), penalty_factor: 0, step: 0 };
+
+        let mut edge_buffer = vec![];
+        gls_fast_local_search(&tsp, &mut state, false, &candidates, &order, &mut edge_buffer, &mut ());
+        assert!(state.route.check_cost(&tsp));
+        assert_eq!(state.best, state.route);
+
+        // No vertex can be linked to one of its candidates with a 2-opt move anymore.
+        let order: Vec<_> = edge_buffer.iter().map(|&(v0, _)| v0).collect();
+        let mut position = vec![0; 40];
+        for (i, &v) in order.iter().enumerate() {
+            position[v] = i;
+        }
+        assert!((0..40).all(|v| fast_local_search_step(&tsp, &order, &position, &candidates[v], v, None).is_none()));
+    }
+
+    #[test]
+    fn reverse() {
+        let mut order = vec![0, 1, 2, 3, 4, 5];
+        let mut position = order.clone();
+        super::reverse(&mut order, &mut position, 4, 1);
+        assert_eq!(order, vec![5, 4, 2, 3, 1, 0]);
+        assert!(order.iter().enumerate().all(|(i, &v)| position[v] == i));
+    }
+
+    #[test]
+    fn config() {
+        let tsp = scattered::<f64>(40);
+        let initial = gls(&tsp, 0).cost;
+
+        let configs = [
+            GlsConfig { aspiration: true, fast: false, ..GlsConfig::default() },
+            GlsConfig { aspiration: true, ..GlsConfig::default() },
+            GlsConfig { candidates: 3, ..GlsConfig::default() },
+            GlsConfig { lambda: 0.1, tie_break: TieBreak::Random(3), decay: Decay::Scale { every: 5, factor: 0.5 }, ..GlsConfig::default() },
+            GlsConfig { utility: Utility::Weight, tie_break: TieBreak::First, increment: 2, decay: Decay::Reset { every: 7 }, ..GlsConfig::default() },
+        ];
+
+        for config in configs.iter() {
+            let route = gls_with_config(&tsp, 30, config);
+            assert!(route.check_cost(&tsp));
+            assert!(route.cost <= initial);
+            assert_eq!(gls_with_config(&tsp, 30, config), route);
+        }
+    }
+}
@@ -5,16 +5,51 @@ use std::iter;
 use tsplib::Tsp;
 use crate::route::Route;
 use crate::path::Path;
+use crate::random::Random;
+use crate::weight::{Weight, WeightError, check_tour_bound};
 
 #[derive(Eq, PartialEq, Clone)]
-pub struct SymmetricMatrix {
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json", serde(try_from = "MatrixData<W>"))]
+#[cfg_attr(feature = "json", serde(bound(deserialize = "W: Weight + serde::Deserialize<'de>")))]
+pub struct SymmetricMatrix<W = u32> {
     size: usize,
-    data: Vec<u32>,
+    data: Vec<W>,
 }
 
-impl SymmetricMatrix {
-    pub fn from_size(size: usize) -> SymmetricMatrix {
-        let data = vec![0u32; size * size];
+/// Unchecked form of a deserialized matrix.
+#[cfg(feature = "json")]
+#[derive(serde::Deserialize)]
+struct MatrixData<W> {
+    size: usize,
+    data: Vec<W>,
+}
+
+#[cfg(feature = "json")]
+impl<W: Weight> std::convert::TryFrom<MatrixData<W>> for SymmetricMatrix<W> {
+    type Error = String;
+
+    fn try_from(MatrixData { size, data }: MatrixData<W>) -> Result<Self, Self::Error> {
+        if data.len() != size * size {
+            return Err(format!("expected {} weights, found {}", size * size, data.len()));
+        }
+
+        let res = Self { size, data };
+        for i in 0..size {
+            for j in i + 1..size {
+                if res[(i, j)] != res[(j, i)] {
+                    return Err(format!("weights of ({}, {}) and ({}, {}) differ", i, j, j, i));
+                }
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+impl<W: Weight> SymmetricMatrix<W> {
+    pub fn from_size(size: usize) -> Self {
+        let data = vec![W::ZERO; size * size];
         Self { size, data }
     }
 
@@ -22,51 +57,80 @@ impl SymmetricMatrix {
         self.size
     }
 
-    fn dist(one: (i32, i32), other: (i32, i32)) -> u32 {
-        let dx = one.0 - other.0;
-        let dy = one.1 - other.1;
-        let res = f64::sqrt(((dx * dx) + (dy * dy)) as f64);
-        res as u32
+    /// Computed in floating point so large coordinates don't overflow.
+    fn dist(one: (i32, i32), other: (i32, i32)) -> f64 {
+        let dx = (one.0 as i64 - other.0 as i64) as f64;
+        let dy = (one.1 as i64 - other.1 as i64) as f64;
+        f64::sqrt((dx * dx) + (dy * dy))
     }
 
     #[inline]
-    pub fn inc(&mut self, index: (usize, usize), value: u32) -> u32 {
+    pub fn inc(&mut self, index: (usize, usize), value: W) -> W {
         let value = self[index] + value;
         self.set(index, value);
         value
     }
 
-    fn from_euc_2d(coords: &[(i32, i32)]) -> Self {
+    /// Panics if the weights overflow. See `try_from_euc_2d`.
+    pub fn from_euc_2d(coords: &[(i32, i32)]) -> Self {
+        Self::try_from_euc_2d(coords).unwrap()
+    }
+
+    /// Fails if an edge or a tour may not fit the weight type.
+    pub fn try_from_euc_2d(coords: &[(i32, i32)]) -> Result<Self, WeightError> {
         let size = coords.len();
         assert!(size > 0);
 
         let mut res = Self::from_size(size);
+        let mut max_weight = 0.0f64;
 
         for (i, point) in coords.iter().copied().enumerate() {
             for (j, neighbor) in coords.iter().copied().enumerate().skip(i + 1) {
                 let dist = Self::dist(point, neighbor);
+                if dist.trunc() > W::MAX.to_f64() {
+                    return Err(WeightError::Edge { edge: (i, j), weight: dist });
+                }
+
+                let dist = W::from_f64(dist);
+                max_weight = max_weight.max(dist.to_f64());
                 res.set((i, j), dist);
             }
         }
 
-        res
+        check_tour_bound::<W>(size, max_weight)?;
+        Ok(res)
     }
 
+    /// Panics if the file isn't supported or the weights overflow. See `try_from_tsplib`.
     pub fn from_tsplib(tsp: &Tsp) -> Self {
+        Self::try_from_tsplib(tsp).unwrap()
+    }
+
+    /// Fails on anything but symmetric problems with 2D euclidean weights, or if the weights overflow.
+    pub fn try_from_tsplib(tsp: &Tsp) -> Result<Self, String> {
         match (tsp.kind, tsp.edge_weight) {
-            (tsplib::Kind::Tsp, tsplib::EdgeWeightKind::Euclidean2d) => { Self::from_euc_2d(&tsp.nodes) }
-            (k, e) => { unimplemented!("Tsplib file not supported. kind: {:?}, edge_weight: {:?}", k, e) }
+            (tsplib::Kind::Tsp, tsplib::EdgeWeightKind::Euclidean2d) => Self::try_from_euc_2d(&tsp.nodes).map_err(|e| e.to_string()),
+            (k, e) => Err(format!("Tsplib file not supported. kind: {:?}, edge_weight: {:?}", k, e)),
         }
     }
 
     #[inline]
     fn get_index(&self, i: usize, j: usize) -> usize {
-        debug_assert!(i < self.size);
-        debug_assert!(j < self.size);
+        assert!(i < self.size && j < self.size, "index out of range: {:?}", (i, j));
         i * self.size + j
     }
 
-    pub fn set(&mut self, index: (usize, usize), value: u32) {
+    /// Same as indexing without checking the vertices.
+    ///
+    /// # Safety
+    /// Both vertices must be smaller than the size of the matrix.
+    #[inline]
+    pub(crate) unsafe fn get_unchecked(&self, (i, j): (usize, usize)) -> W {
+        debug_assert!(i < self.size && j < self.size);
+        *self.data.get_unchecked(i * self.size + j)
+    }
+
+    pub fn set(&mut self, index: (usize, usize), value: W) {
         let (i, j) = index;
 
         let ia = self.get_index(i, j);
@@ -76,13 +140,16 @@ impl SymmetricMatrix {
         self.data[ib] = value;
     }
 
-    pub fn cost(&self, path: &Path) -> u32 {
+    pub fn cost(&self, path: &Path) -> W {
+        assert_eq!(path.size(), self.size);
+
+        // Safe since the vertices of the path are smaller than its size.
         path.edges_visited()
-            .map(|edge| self[edge])
+            .map(|edge| unsafe { self.get_unchecked(edge) })
             .sum()
     }
 
-    pub fn sequential(&self) -> Route {
+    pub fn sequential(&self) -> Route<W> {
         let path = (0..self.size - 1)
             .map(|i| (i, (i + 2) % self.size));
         let path = iter::once((1, self.size - 1)).chain(path);
@@ -90,10 +157,10 @@ impl SymmetricMatrix {
         let path = Path::new(path);
 
         let cost = self.cost(&path);
-        Route { cost, path }
+        Route::new(cost, path)
     }
 
-    pub fn nearest_neighbor(&self) -> Route {
+    pub fn nearest_neighbor(&self) -> Route<W> {
         let size = self.size;
 
         let mut path = Path::uninitialized(size);
@@ -105,7 +172,7 @@ impl SymmetricMatrix {
             let (remainder, neighbor) = remainders.iter().copied()
                 .enumerate()
                 .min_by(|&(_, n_a), &(_, n_b)|
-                    self[(vertex, n_a)].cmp(&self[(vertex, n_b)])
+                    self[(vertex, n_a)].partial_cmp(&self[(vertex, n_b)]).unwrap()
                 )
                 .unwrap();
 
@@ -120,21 +187,132 @@ impl SymmetricMatrix {
         debug_assert!(path.is_hamiltonian());
         Route::new(cost, path)
     }
+
+    /// Nearest neighbor route from a random vertex, going to one of the three nearest unvisited candidates at random.
+    pub(crate) fn randomized_neighbor(&self, candidates: &[Vec<usize>], random: &mut Random) -> Route<W> {
+        let size = self.size;
+        let mut visited = vec![false; size];
+        let mut order = Vec::with_capacity(size);
+        let mut vertex = random.below(size);
+
+        loop {
+            visited[vertex] = true;
+            order.push(vertex);
+            if order.len() == size { break; }
+
+            let near: Vec<_> = candidates[vertex].iter().copied().filter(|&v| !visited[v]).take(3).collect();
+            vertex = if near.is_empty() {
+                (0..size).filter(|&v| !visited[v])
+                    .min_by(|&a, &b| self[(vertex, a)].partial_cmp(&self[(vertex, b)]).unwrap())
+                    .unwrap()
+            } else {
+                near[random.below(near.len())]
+            };
+        }
+
+        let path = Path::from_order(&order);
+        let cost = self.cost(&path);
+        Route::new(cost, path)
+    }
+
+    /// The `k` nearest vertices of each vertex, nearest first. Ties go to the smallest vertex.
+    pub fn candidates(&self, k: usize) -> Vec<Vec<usize>> {
+        (0..self.size)
+            .map(|v| {
+                let mut neighbors: Vec<_> = (0..self.size).filter(|&u| u != v).collect();
+                neighbors.sort_by(|&a, &b| self[(v, a)].partial_cmp(&self[(v, b)]).unwrap().then(a.cmp(&b)));
+                neighbors.truncate(k);
+                neighbors
+            })
+            .collect()
+    }
+}
+
+/// Weights that may differ in each direction, like the ones of sequential ordering problems.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct AsymmetricMatrix<W = u32> {
+    size: usize,
+    data: Vec<W>,
+}
+
+impl<W: Weight> AsymmetricMatrix<W> {
+    pub fn from_size(size: usize) -> Self {
+        let data = vec![W::ZERO; size * size];
+        Self { size, data }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Sets the weight of going from the first vertex to the second only.
+    pub fn set(&mut self, (i, j): (usize, usize), value: W) {
+        assert!(i < self.size && j < self.size, "index out of range: {:?}", (i, j));
+        self.data[i * self.size + j] = value;
+    }
+}
+
+impl<W: Weight> From<&SymmetricMatrix<W>> for AsymmetricMatrix<W> {
+    fn from(tsp: &SymmetricMatrix<W>) -> Self {
+        Self { size: tsp.size, data: tsp.data.clone() }
+    }
 }
 
-impl Index<(usize, usize)> for SymmetricMatrix {
-    type Output = u32;
+impl<W: Weight> Index<(usize, usize)> for AsymmetricMatrix<W> {
+    type Output = W;
+
+    fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
+        assert!(i < self.size && j < self.size, "index out of range: {:?}", (i, j));
+        &self.data[i * self.size + j]
+    }
+}
+
+/// Weights the local search can run over, either stored or computed.
+pub trait EdgeWeights<W: Weight>: Sync {
+    fn size(&self) -> usize;
+
+    fn weight(&self, edge: (usize, usize)) -> W;
+
+    /// Same as `weight` without checking the vertices.
+    ///
+    /// # Safety
+    /// Both vertices must be smaller than `size`.
+    unsafe fn weight_unchecked(&self, edge: (usize, usize)) -> W {
+        self.weight(edge)
+    }
+}
+
+impl<W: Weight> EdgeWeights<W> for SymmetricMatrix<W> {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    fn weight(&self, edge: (usize, usize)) -> W {
+        self[edge]
+    }
+
+    #[inline]
+    unsafe fn weight_unchecked(&self, edge: (usize, usize)) -> W {
+        self.get_unchecked(edge)
+    }
+}
+
+impl<W: Weight> Index<(usize, usize)> for SymmetricMatrix<W> {
+    type Output = W;
 
     fn index(&self, index: (usize, usize)) -> &Self::Output {
         let (i, j) = index;
         let index = self.get_index(i, j);
+
+        // Safe since `get_index` checks the range.
         unsafe { self.data.get_unchecked(index) }
     }
 }
 
-impl Display for SymmetricMatrix {
+impl<W: Weight> Display for SymmetricMatrix<W> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let precision = f.precision().unwrap_or(self.size);
+        let precision = f.precision().unwrap_or(self.size).min(self.size);
 
         let precision_fmt = if precision == self.size {
             ":     ".to_owned()
@@ -348,5 +526,38 @@ mod tests {
 
             assert_eq!(actual, expected);
         }
+
+        #[test]
+        fn candidates() {
+            let candidates = matrix().candidates(2);
+            assert_eq!(candidates[0], vec![1, 2]);
+            assert_eq!(candidates[3], vec![2, 1]);
+            assert_eq!(candidates[4], vec![0, 2]);
+        }
+    }
+
+    mod weight {
+        use crate::matrix::SymmetricMatrix;
+        use crate::path::Path;
+        use crate::weight::WeightError;
+
+        const FAR: [(i32, i32); 3] = [(-2_000_000_000, 0), (2_000_000_000, 0), (0, 2_000_000_000)];
+
+        #[test]
+        fn large_coordinates() {
+            let matrix = SymmetricMatrix::<u64>::from_euc_2d(&FAR);
+            assert_eq!(matrix[(0, 1)], 4_000_000_000);
+            assert_eq!(matrix.cost(&Path::from_order(&[0, 1, 2])), 4_000_000_000 + 2 * 2_828_427_124);
+        }
+
+        #[test]
+        fn overflow() {
+            let coords = [(0, 0), (-2_000_000_000, -2_000_000_000), (2_000_000_000, 2_000_000_000)];
+            let error = SymmetricMatrix::<u32>::try_from_euc_2d(&coords).err().unwrap();
+            assert!(matches!(error, WeightError::Edge { edge: (1, 2), .. }));
+
+            let error = SymmetricMatrix::<u32>::try_from_euc_2d(&FAR).err().unwrap();
+            assert!(matches!(error, WeightError::Tour { size: 3, .. }));
+        }
     }
 }
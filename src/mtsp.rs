@@ -0,0 +1,307 @@
+use std::fmt::{Display, Formatter};
+use std::fmt;
+use crate::matrix::SymmetricMatrix;
+
+/// What the salesmen minimize together.
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum Objective {
+    /// Sum of the length of all tours.
+    Total,
+    /// Length of the longest tour.
+    MinMax,
+}
+
+/// Solution with one closed tour per salesman. All tours start and end at the depot, vertex 0,
+/// which is not stored in `tours`.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct MultiRoute {
+    pub cost: u32,
+    pub tour_costs: Vec<u32>,
+    pub tours: Vec<Vec<usize>>,
+}
+
+#[inline]
+fn prev(tour: &[usize], pos: usize) -> usize {
+    if pos == 0 { 0 } else { tour[pos - 1] }
+}
+
+#[inline]
+fn next(tour: &[usize], pos: usize) -> usize {
+    if pos + 1 == tour.len() { 0 } else { tour[pos + 1] }
+}
+
+/// Vertex at the position of the tour including the depot at both ends.
+#[inline]
+fn closed(tour: &[usize], pos: usize) -> usize {
+    if pos == 0 || pos > tour.len() { 0 } else { tour[pos - 1] }
+}
+
+fn tour_cost(tsp: &SymmetricMatrix, tour: &[usize]) -> u32 {
+    (0..=tour.len())
+        .map(|pos| tsp[(closed(tour, pos), closed(tour, pos + 1))])
+        .sum()
+}
+
+impl Objective {
+    /// Value of the objective. Ties on the longest tour are broken by the total.
+    fn score(self, tour_costs: impl Iterator<Item=u32>) -> (u32, u32) {
+        let (max, total) = tour_costs.fold((0, 0), |(max, total), c| (max.max(c), total + c));
+        match self {
+            Objective::Total => (total, 0),
+            Objective::MinMax => (max, total),
+        }
+    }
+
+    /// Score if the tours `i` and `j` had their costs changed.
+    fn score_with(self, tour_costs: &[u32], (i, ci): (usize, u32), (j, cj): (usize, u32)) -> (u32, u32) {
+        let costs = tour_costs.iter().copied()
+            .enumerate()
+            .map(|(k, c)| if k == i { ci } else if k == j { cj } else { c });
+        self.score(costs)
+    }
+}
+
+impl MultiRoute {
+    pub fn new(tsp: &SymmetricMatrix, tours: Vec<Vec<usize>>, objective: Objective) -> Self {
+        let tour_costs: Vec<_> = tours.iter().map(|t| tour_cost(tsp, t)).collect();
+        let cost = objective.score(tour_costs.iter().copied()).0;
+        Self { cost, tour_costs, tours }
+    }
+
+    pub fn salesmen(&self) -> usize {
+        self.tours.len()
+    }
+
+    pub fn total(&self) -> u32 {
+        self.tour_costs.iter().sum()
+    }
+
+    /// Check if every vertex except the depot is visited by exactly one salesman.
+    pub fn is_partition(&self, size: usize) -> bool {
+        let mut visited = vec![false; size];
+        visited[0] = true;
+
+        for &v in self.tours.iter().flatten() {
+            if v == 0 || v >= size || visited[v] { return false; }
+            visited[v] = true;
+        }
+
+        visited.into_iter().all(|v| v)
+    }
+
+    /// Splits the nearest neighbor tour in `salesmen` contiguous pieces with about the same number of vertices.
+    pub fn split_nearest_neighbor(tsp: &SymmetricMatrix, salesmen: usize, objective: Objective) -> Self {
+        assert!(salesmen > 0);
+
+        let route = tsp.nearest_neighbor();
+        let order: Vec<_> = route.path.vertices_visited().filter(|&v| v != 0).collect();
+
+        let chunk = order.len().div_ceil(salesmen);
+        let mut tours: Vec<_> = order.chunks(chunk.max(1)).map(|c| c.to_vec()).collect();
+        tours.resize(salesmen, vec![]);
+
+        Self::new(tsp, tours, objective)
+    }
+
+    fn apply(&mut self, objective: Objective, (i, ci): (usize, u32), (j, cj): (usize, u32)) {
+        self.tour_costs[i] = ci;
+        self.tour_costs[j] = cj;
+        self.cost = objective.score(self.tour_costs.iter().copied()).0;
+    }
+}
+
+/// Reverses a segment inside a single tour.
+fn two_opt(tsp: &SymmetricMatrix, route: &mut MultiRoute, objective: Objective) -> bool {
+    let current = objective.score(route.tour_costs.iter().copied());
+
+    for i in 0..route.salesmen() {
+        let tour = &route.tours[i];
+        let len = tour.len();
+
+        for p in 0..len {
+            for q in p + 1..=len {
+                let (a0, a1) = (closed(tour, p), closed(tour, p + 1));
+                let (b0, b1) = (closed(tour, q), closed(tour, q + 1));
+
+                let cost = route.tour_costs[i] + tsp[(a0, b0)] + tsp[(a1, b1)] - tsp[(a0, a1)] - tsp[(b0, b1)];
+                if objective.score_with(&route.tour_costs, (i, cost), (i, cost)) < current {
+                    route.tours[i][p..q].reverse();
+                    route.apply(objective, (i, cost), (i, cost));
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Moves a vertex from one tour to another.
+fn relocate(tsp: &SymmetricMatrix, route: &mut MultiRoute, objective: Objective) -> bool {
+    let current = objective.score(route.tour_costs.iter().copied());
+
+    for i in 0..route.salesmen() {
+        for p in 0..route.tours[i].len() {
+            let from = &route.tours[i];
+            let v = from[p];
+            let (before, after) = (prev(from, p), next(from, p));
+            let ci = route.tour_costs[i] + tsp[(before, after)] - tsp[(before, v)] - tsp[(v, after)];
+
+            for j in (0..route.salesmen()).filter(|&j| j != i) {
+                let to = &route.tours[j];
+
+                for q in 0..=to.len() {
+                    let (before, after) = (closed(to, q), closed(to, q + 1));
+                    let cj = route.tour_costs[j] + tsp[(before, v)] + tsp[(v, after)] - tsp[(before, after)];
+
+                    if objective.score_with(&route.tour_costs, (i, ci), (j, cj)) < current {
+                        route.tours[i].remove(p);
+                        route.tours[j].insert(q, v);
+                        route.apply(objective, (i, ci), (j, cj));
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Exchanges two vertices of different tours.
+fn swap(tsp: &SymmetricMatrix, route: &mut MultiRoute, objective: Objective) -> bool {
+    let current = objective.score(route.tour_costs.iter().copied());
+
+    let replace = |tour: &[usize], pos: usize, cost: u32, vertex: usize| -> u32 {
+        let (before, old, after) = (prev(tour, pos), tour[pos], next(tour, pos));
+        cost + tsp[(before, vertex)] + tsp[(vertex, after)] - tsp[(before, old)] - tsp[(old, after)]
+    };
+
+    for i in 0..route.salesmen() {
+        for j in i + 1..route.salesmen() {
+            for p in 0..route.tours[i].len() {
+                for q in 0..route.tours[j].len() {
+                    let (u, w) = (route.tours[i][p], route.tours[j][q]);
+                    let ci = replace(&route.tours[i], p, route.tour_costs[i], w);
+                    let cj = replace(&route.tours[j], q, route.tour_costs[j], u);
+
+                    if objective.score_with(&route.tour_costs, (i, ci), (j, cj)) < current {
+                        route.tours[i][p] = w;
+                        route.tours[j][q] = u;
+                        route.apply(objective, (i, ci), (j, cj));
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Exchanges the tails of two tours (2-opt*).
+fn cross(tsp: &SymmetricMatrix, route: &mut MultiRoute, objective: Objective) -> bool {
+    let current = objective.score(route.tour_costs.iter().copied());
+
+    for i in 0..route.salesmen() {
+        for j in i + 1..route.salesmen() {
+            let (ti, tj) = (&route.tours[i], &route.tours[j]);
+            let (tails_i, tails_j) = (tails(tsp, ti), tails(tsp, tj));
+
+            // Cut after the first `p` vertices of `i` and the first `q` vertices of `j`.
+            for (p, &tail_i) in tails_i.iter().enumerate() {
+                for (q, &tail_j) in tails_j.iter().enumerate() {
+                    let (a0, a1) = (closed(ti, p), closed(ti, p + 1));
+                    let (b0, b1) = (closed(tj, q), closed(tj, q + 1));
+                    if a1 == 0 && b1 == 0 { continue; }
+
+                    let ci = route.tour_costs[i] + tsp[(a0, b1)] + tail_j - tsp[(a0, a1)] - tail_i;
+                    let cj = route.tour_costs[j] + tsp[(b0, a1)] + tail_i - tsp[(b0, b1)] - tail_j;
+
+                    if objective.score_with(&route.tour_costs, (i, ci), (j, cj)) < current {
+                        let tail_i = route.tours[i].split_off(p);
+                        let tail_j = route.tours[j].split_off(q);
+                        route.tours[i].extend(tail_j);
+                        route.tours[j].extend(tail_i);
+                        route.apply(objective, (i, ci), (j, cj));
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Cost of the tour after each of its positions, excluding the edge entering it.
+fn tails(tsp: &SymmetricMatrix, tour: &[usize]) -> Vec<u32> {
+    let mut res = vec![0; tour.len() + 1];
+    for pos in (0..tour.len()).rev() {
+        res[pos] = res[pos + 1] + tsp[(closed(tour, pos + 1), closed(tour, pos + 2))];
+    }
+    res
+}
+
+/// Applies improving intra and inter-tour moves until none is left.
+pub fn local_search(tsp: &SymmetricMatrix, candidate: &mut MultiRoute, objective: Objective) {
+    while two_opt(tsp, candidate, objective)
+        || relocate(tsp, candidate, objective)
+        || swap(tsp, candidate, objective)
+        || cross(tsp, candidate, objective) {}
+
+    debug_assert_eq!(candidate, &MultiRoute::new(tsp, candidate.tours.clone(), objective));
+}
+
+/// Solves the multiple TSP with `salesmen` tours sharing the depot 0.
+pub fn mtsp(tsp: &SymmetricMatrix, salesmen: usize, objective: Objective) -> MultiRoute {
+    let mut route = MultiRoute::split_nearest_neighbor(tsp, salesmen, objective);
+    local_search(tsp, &mut route, objective);
+
+    debug_assert!(route.is_partition(tsp.size()));
+    route
+}
+
+impl Display for MultiRoute {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "MultiRoute {{ cost: {}, tours: {:?} }}", self.cost, self.tours)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::SymmetricMatrix;
+    use crate::mtsp::{mtsp, Objective};
+
+    /// Depot in the middle of two clusters.
+    fn matrix() -> SymmetricMatrix {
+        SymmetricMatrix::from_euc_2d(&[
+            (0, 0),
+            (100, 0), (-100, 1), (110, 10), (-110, 0), (100, 20), (-100, 20),
+        ])
+    }
+
+    #[test]
+    fn total() {
+        let tsp = matrix();
+        let route = mtsp(&tsp, 2, Objective::Total);
+
+        assert!(route.is_partition(tsp.size()));
+        assert_eq!(route.cost, route.total());
+    }
+
+    #[test]
+    fn min_max() {
+        let tsp = matrix();
+        let route = mtsp(&tsp, 2, Objective::MinMax);
+
+        assert!(route.is_partition(tsp.size()));
+        assert_eq!(route.cost, *route.tour_costs.iter().max().unwrap());
+
+        // Each salesman takes one of the clusters: odd vertices on the right and even on the left.
+        for tour in route.tours.iter() {
+            assert_eq!(tour.len(), 3, "{}", route);
+            assert!(tour.iter().all(|&v| v % 2 == tour[0] % 2), "{}", route);
+        }
+    }
+}
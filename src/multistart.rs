@@ -0,0 +1,181 @@
+use rayon::prelude::*;
+use crate::checkpoint::GlsState;
+use crate::config::GlsConfig;
+use crate::matrix::SymmetricMatrix;
+use crate::random::Random;
+use crate::route::Route;
+use crate::weight::Weight;
+use crate::{gls_finish, gls_local_search, gls_steps};
+
+/// What the instances do with the best route found by any of them each time they share it.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Sharing {
+    /// Nothing, the instances only meet at the end.
+    None,
+    /// Instances whose best route is worse continue from the shared one, keeping their penalties.
+    Adopt,
+    /// The other instances penalize the edges of the shared route once more, to search away from it.
+    Penalize,
+    /// The other instances drop the penalties of the edges of the shared route, so they tend to keep them.
+    Fix,
+}
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct MultiStartConfig {
+    pub instances: usize,
+    /// Steps each instance runs between two shares.
+    pub share_every: usize,
+    pub sharing: Sharing,
+    /// Seed of the initial routes. The first instance starts from the nearest neighbor route, the others from randomized ones.
+    pub seed: u64,
+}
+
+impl Default for MultiStartConfig {
+    fn default() -> Self {
+        Self { instances: 4, share_every: 100, sharing: Sharing::Adopt, seed: 0 }
+    }
+}
+
+/// The instances other than the one with the cheapest best route take it as `sharing` says,
+/// and those that changed search again from their route. Returns the index of the leader.
+fn share<W: Weight>(tsp: &SymmetricMatrix<W>, states: &mut [GlsState<W>], config: &GlsConfig, sharing: Sharing) -> usize {
+    let (leader, shared) = states.iter().enumerate()
+        .min_by(|(_, a), (_, b)| a.best.cost.partial_cmp(&b.best.cost).unwrap())
+        .map(|(i, state)| (i, state.best.clone()))
+        .unwrap();
+    let edges: Vec<_> = shared.path.edges_visited().collect();
+
+    states.par_iter_mut().enumerate().filter(|&(i, _)| i != leader).for_each(|(_, state)| {
+        match sharing {
+            Sharing::None => return,
+            Sharing::Adopt if shared.cost < state.best.cost => {
+                state.route = shared.clone();
+                state.best = shared.clone();
+            }
+            Sharing::Adopt => return,
+            Sharing::Penalize => for &edge in edges.iter() {
+                state.penalties.inc(edge, config.increment);
+            },
+            Sharing::Fix => for &edge in edges.iter() {
+                state.penalties.set(edge, 0);
+            },
+        }
+
+        // The fast local search only looks around the edges it penalizes, so the changes are searched in full.
+        gls_local_search(tsp, state, config.aspiration, &mut Vec::with_capacity(tsp.size()), &mut ());
+    });
+
+    leader
+}
+
+/// Runs independent guided local searches across threads, each for `steps` steps, and returns the best route.
+/// The instances stop together every `share_every` steps to share their best route, so runs are deterministic.
+pub fn multi_start_gls<W: Weight>(tsp: &SymmetricMatrix<W>, steps: usize, config: &GlsConfig, multi: &MultiStartConfig) -> Route<W> {
+    assert!(multi.instances > 0);
+
+    let mut random = Random::new(multi.seed);
+    let candidates = tsp.candidates(config.candidates);
+    let mut states: Vec<_> = (0..multi.instances)
+        .map(|i| if i == 0 { tsp.nearest_neighbor() } else { tsp.randomized_neighbor(&candidates, &mut random) })
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|route| GlsState::from_route(tsp, route, config, &mut ()))
+        .collect();
+
+    let every = if multi.share_every > 0 { multi.share_every } else { steps.max(1) };
+    let mut done = 0;
+    while done < steps {
+        done = (done + every).min(steps);
+        states.par_iter_mut().for_each(|state| gls_steps(tsp, state, done, config, &mut (), 0, |_| {}));
+        if done == steps { break; }
+
+        share(tsp, &mut states, config, multi.sharing);
+    }
+
+    states.into_par_iter()
+        .map(|state| gls_finish(tsp, state, &mut ()))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .min_by(|a, b| a.cost.partial_cmp(&b.cost).unwrap())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checkpoint::GlsState;
+    use crate::config::GlsConfig;
+    use crate::fixture::scattered;
+    use crate::{gls_finish, gls_steps, gls_with_config};
+    use crate::multistart::{multi_start_gls, share, MultiStartConfig, Sharing};
+    use crate::random::Random;
+
+    #[test]
+    fn share_best() {
+        let tsp = scattered::<u32>(50);
+        let config = GlsConfig::default();
+        let candidates = tsp.candidates(10);
+        let mut random = Random::new(7);
+
+        // Instances with penalties on every edge of their route, after a few steps.
+        let instances: Vec<_> = (0..4)
+            .map(|_| {
+                let mut state = GlsState::from_route(&tsp, tsp.randomized_neighbor(&candidates, &mut random), &config, &mut ());
+                for edge in state.route.path.edges_visited() {
+                    state.penalties.set(edge, 2);
+                }
+                state
+            })
+            .collect();
+        let best = instances.iter().map(|s| s.best.cost).min().unwrap();
+
+        let mut states = instances.clone();
+        let leader = share(&tsp, &mut states, &config, Sharing::None);
+        assert_eq!(states[leader].best.cost, best);
+        assert!(states == instances);
+
+        let mut states = instances.clone();
+        share(&tsp, &mut states, &config, Sharing::Adopt);
+        assert!(states.iter().all(|s| s.best.cost <= best));
+        assert!(states.iter().zip(instances.iter()).any(|(s, i)| s.best != i.best));
+        assert!(states.iter().zip(instances.iter()).all(|(s, i)| s.penalties == i.penalties));
+
+        let edges: Vec<_> = instances[leader].best.path.edges_visited().collect();
+        let mut states = instances.clone();
+        share(&tsp, &mut states, &config, Sharing::Penalize);
+        for (i, (state, before)) in states.iter().zip(instances.iter()).enumerate().filter(|&(i, _)| i != leader) {
+            assert!(edges.iter().all(|&e| state.penalties.get(e) == before.penalties.get(e) + config.increment), "instance {}", i);
+        }
+
+        let mut states = instances.clone();
+        share(&tsp, &mut states, &config, Sharing::Fix);
+        assert!(states[leader] == instances[leader]);
+        assert!(states.iter().enumerate().filter(|&(i, _)| i != leader).all(|(_, s)| edges.iter().all(|&e| s.penalties.get(e) == 0)));
+    }
+
+    #[test]
+    fn best_instance() {
+        let tsp = scattered::<f64>(50);
+        let config = GlsConfig { candidates: 6, ..GlsConfig::default() };
+
+        // Instances that never share run as if alone, from the same starting routes.
+        let multi = MultiStartConfig { instances: 4, share_every: 5, sharing: Sharing::None, seed: 1 };
+        let candidates = tsp.candidates(config.candidates);
+        let mut random = Random::new(multi.seed);
+        let alone: Vec<_> = (0..multi.instances)
+            .map(|i| {
+                let route = if i == 0 { tsp.nearest_neighbor() } else { tsp.randomized_neighbor(&candidates, &mut random) };
+                let mut state = GlsState::from_route(&tsp, route, &config, &mut ());
+                gls_steps(&tsp, &mut state, 20, &config, &mut (), 0, |_| {});
+                gls_finish(&tsp, state, &mut ())
+            })
+            .collect();
+
+        let best = alone.iter().min_by(|a, b| a.cost.partial_cmp(&b.cost).unwrap()).unwrap();
+        assert!(alone.iter().any(|r| r.cost > best.cost));
+        assert_eq!(&multi_start_gls(&tsp, 20, &config, &multi), best);
+
+        // A single instance that never shares is the plain search.
+        let single = MultiStartConfig { instances: 1, sharing: Sharing::None, ..MultiStartConfig::default() };
+        assert_eq!(multi_start_gls(&tsp, 20, &config, &single), gls_with_config(&tsp, 20, &config));
+    }
+}
@@ -1,8 +1,13 @@
-use std::ops::{Index, IndexMut};
+use std::ops::Index;
 use std::{mem, fmt};
+use std::error::Error;
 use std::fmt::{Display, Formatter};
 
-#[derive(Eq, PartialEq, Debug)]
+/// Adjacency of each vertex in the cycle.
+/// Every vertex stored in it is smaller than its length, so it can be walked without bound checks.
+#[derive(Eq, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json", serde(try_from = "Vec<(usize, usize)>"))]
 pub struct Path(Vec<(usize, usize)>);
 
 #[derive(Eq, PartialEq, Debug, Copy, Clone)]
@@ -12,6 +17,26 @@ pub enum HamiltonianResult {
     NotVisited(usize),
 }
 
+/// Invalid input when building or changing a path.
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum PathError {
+    TooSmall(usize),
+    OutOfRange(usize),
+    Repeated(usize),
+    NotAnEdge(usize, usize),
+    NotHamiltonian(HamiltonianResult),
+}
+
+/// Paths read from outside are checked like `try_new`.
+#[cfg(feature = "json")]
+impl std::convert::TryFrom<Vec<(usize, usize)>> for Path {
+    type Error = PathError;
+
+    fn try_from(data: Vec<(usize, usize)>) -> Result<Self, Self::Error> {
+        Self::try_new(data)
+    }
+}
+
 impl Path
 {
     pub fn uninitialized(size: usize) -> Self {
@@ -21,11 +46,65 @@ impl Path
 
     pub fn new(data: Vec<(usize, usize)>) -> Self {
         assert!(data.len() > 1);
+        assert!(data.iter().all(|&(v0, v1)| v0 < data.len() && v1 < data.len()));
         Self(data)
     }
 
+    /// Same as `new`, but checks that the adjacency is a Hamiltonian cycle.
+    pub fn try_new(data: Vec<(usize, usize)>) -> Result<Self, PathError> {
+        let size = data.len();
+        if size < 2 { return Err(PathError::TooSmall(size)); }
+
+        if let Some(&(v0, v1)) = data.iter().find(|&&(v0, v1)| v0 >= size || v1 >= size) {
+            return Err(PathError::OutOfRange(v0.max(v1)));
+        }
+
+        let path = Self(data);
+        match path.check_hamiltonian_fast() {
+            HamiltonianResult::Ok => Ok(path),
+            result => Err(PathError::NotHamiltonian(result)),
+        }
+    }
+
+    /// Same as `from_order`, but checks that the order is a permutation.
+    pub fn try_from_order(order: &[usize]) -> Result<Self, PathError> {
+        let size = order.len();
+        if size < 2 { return Err(PathError::TooSmall(size)); }
+
+        let mut visited = vec![false; size];
+        for &v in order {
+            if v >= size { return Err(PathError::OutOfRange(v)); }
+            if visited[v] { return Err(PathError::Repeated(v)); }
+            visited[v] = true;
+        }
+
+        Ok(Self::from_order(order))
+    }
+
+    /// Number of vertices of the problem, including the dropped ones.
+    pub fn size(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Path visiting the vertices in the given order and closing back to the first one.
+    pub fn from_order(order: &[usize]) -> Self {
+        let mut path = Self::uninitialized(order.len());
+
+        for (&v0, &v1) in order.iter().zip(order.iter().skip(1)) {
+            path.init_edge(v0, v1);
+        }
+        path.init_edge(order[order.len() - 1], order[0]);
+
+        path
+    }
+
+    #[inline]
+    fn adj_mut(&mut self, vertex: usize) -> &mut (usize, usize) {
+        &mut self.0[vertex]
+    }
+
     fn internal_init_edge(&mut self, v0: usize, v1: usize) {
-        let edge = &mut self[v0];
+        let edge = self.adj_mut(v0);
 
         let vertex = if edge.0 == 0 { &mut edge.0 } else { &mut edge.1 };
         *vertex = v1;
@@ -36,6 +115,7 @@ impl Path
     }
 
     pub fn init_edge(&mut self, v0: usize, v1: usize) {
+        assert!(v0 < self.0.len() && v1 < self.0.len());
         self.internal_init_edge(v0, v1);
         self.internal_init_edge(v1, v0);
     }
@@ -48,6 +128,7 @@ impl Path
 
         debug_assert!(*vertex < self.0.len());
 
+        // Safe since every vertex in the adjacency is in range.
         let going_to = unsafe { self.0.get_unchecked(*vertex) };
         let going_to = if going_to.0 != *coming_from { going_to.0 } else { going_to.1 };
 
@@ -58,17 +139,17 @@ impl Path
     }
 
     /// Vertices visited by the path ending in 0.
-    pub fn vertices_visited(&self) -> VerticesVisited {
+    pub fn vertices_visited(&self) -> VerticesVisited<'_> {
         VerticesVisited {
-            path: &self,
+            path: self,
             coming_from: self.0.len() + 1,
             vertex: 0,
         }
     }
 
-    pub fn edges_visited_after(&self, coming_from: usize, vertex: usize) -> EdgesVisited {
+    pub fn edges_visited_after(&self, coming_from: usize, vertex: usize) -> EdgesVisited<'_> {
         let mut res = EdgesVisited {
-            path: &self,
+            path: self,
             coming_from,
             vertex,
         };
@@ -77,9 +158,9 @@ impl Path
     }
 
     /// Edges visited by the path starting in (0, x) and ending in (y, 0).
-    pub fn edges_visited(&self) -> EdgesVisited {
+    pub fn edges_visited(&self) -> EdgesVisited<'_> {
         EdgesVisited {
-            path: &self,
+            path: self,
             coming_from: self.0.len() + 1,
             vertex: 0,
         }
@@ -99,19 +180,19 @@ impl Path
 
             let adj0 = self[adj.0];
             if adj0.0 != vertex && adj0.1 != vertex {
-                return dbg!(HamiltonianResult::NoEdgeBack(vertex, adj.0, adj0));
+                return HamiltonianResult::NoEdgeBack(vertex, adj.0, adj0);
             };
 
             let adj1 = self[adj.1];
             if adj1.0 != vertex && adj1.1 != vertex {
-                return dbg!(HamiltonianResult::NoEdgeBack(vertex, adj.1, adj1));
+                return HamiltonianResult::NoEdgeBack(vertex, adj.1, adj1);
             };
         }
 
         // Check if all vertices are present
         for vertex in 0..self.0.len() {
             if !self.vertices_visited().any(|v| v == vertex) {
-                return dbg!(HamiltonianResult::NotVisited(vertex));
+                return HamiltonianResult::NotVisited(vertex);
             }
         }
 
@@ -122,9 +203,93 @@ impl Path
         self.check_hamiltonian() == HamiltonianResult::Ok
     }
 
+    /// Same as `check_hamiltonian` in O(n) and without printing.
+    pub fn check_hamiltonian_fast(&self) -> HamiltonianResult {
+        for vertex in 0..self.0.len() {
+            let adj = self[vertex];
+
+            for &neighbor in [adj.0, adj.1].iter() {
+                let back = self[neighbor];
+                if back.0 != vertex && back.1 != vertex {
+                    return HamiltonianResult::NoEdgeBack(vertex, neighbor, back);
+                }
+            }
+        }
+
+        // Every vertex links back, so the walk from 0 is a cycle.
+        let mut visited = vec![false; self.0.len()];
+        for v in self.vertices_visited() {
+            visited[v] = true;
+        }
+
+        match visited.into_iter().position(|v| !v) {
+            Some(vertex) => HamiltonianResult::NotVisited(vertex),
+            None => HamiltonianResult::Ok,
+        }
+    }
+
+    /// Whether the vertices are linked in the path.
+    pub fn has_edge(&self, (v0, v1): (usize, usize)) -> bool {
+        v0 < self.0.len() && v1 < self.0.len() && (self[v0].0 == v1 || self[v0].1 == v1)
+    }
+
+    /// Check if the vertices in the path form a single cycle through 0. Dropped vertices are ignored.
+    pub fn is_cycle(&self) -> bool {
+        let links_back = (0..self.0.len())
+            .filter(|&v| self.contains(v))
+            .all(|v| {
+                let adj = self[v];
+                let (adj0, adj1) = (self[adj.0], self[adj.1]);
+                (adj0.0 == v || adj0.1 == v) && (adj1.0 == v || adj1.1 == v)
+            });
+
+        links_back && self.vertices_visited().count() == self.vertex_count()
+    }
+
+    /// Path with only the vertex 0 in it. Every other vertex is dropped.
+    pub fn singleton(size: usize) -> Self {
+        assert!(size > 1);
+        Self((0..size).map(|v| (v, v)).collect())
+    }
+
+    /// Whether the vertex is in the path. Dropped vertices link to themselves.
+    #[inline]
+    pub fn contains(&self, vertex: usize) -> bool {
+        vertex == 0 || self[vertex] != (vertex, vertex)
+    }
+
+    /// Number of vertices in the path.
+    pub fn vertex_count(&self) -> usize {
+        (0..self.0.len()).filter(|&v| self.contains(v)).count()
+    }
+
+    /// Removes the vertex from the path linking its neighbors together.
+    pub fn drop_vertex(&mut self, vertex: usize) {
+        debug_assert!(vertex != 0 && self.contains(vertex));
+
+        let (v0, v1) = self[vertex];
+        self.twist_helper(v0, vertex, v1);
+        self.twist_helper(v1, vertex, v0);
+        *self.adj_mut(vertex) = (vertex, vertex);
+
+        debug_assert!(self.is_cycle(), "not a cycle after dropping: {:?}", vertex);
+    }
+
+    /// Inserts a dropped vertex between the vertices of an edge.
+    pub fn insert_vertex(&mut self, vertex: usize, (v0, v1): (usize, usize)) {
+        debug_assert!(!self.contains(vertex));
+        assert!(v0 < self.0.len() && v1 < self.0.len());
+
+        self.twist_helper(v0, v1, vertex);
+        self.twist_helper(v1, v0, vertex);
+        *self.adj_mut(vertex) = (v0, v1);
+
+        debug_assert!(self.is_cycle(), "not a cycle after inserting: {:?}", (vertex, (v0, v1)));
+    }
+
     #[inline]
     fn twist_helper(&mut self, v0: usize, v1: usize, value: usize) {
-        let adj = &mut self[v0];
+        let adj = self.adj_mut(v0);
         let adj = if adj.0 == v1 { &mut adj.0 } else { &mut adj.1 };
         debug_assert_eq!(*adj, v1);
         *adj = value;
@@ -132,37 +297,77 @@ impl Path
 
     /// Twist two edges. Visualization (:: implies an indirect connection):
     ///
-    /// ```
+    /// ```text
     /// a0 — a1      a0   a1      a0 — b0
     /// ::   ::  ->  :: x ::  or  ::   ::
     /// b1 — b0      b1   b0      b1 — a1
     /// ```
     #[inline]
-    pub fn twist(&mut self, (a0, a1): (usize, usize), (b0, b1): (usize, usize)) {
+    pub fn twist(&mut self, a: (usize, usize), b: (usize, usize)) {
+        self.swap_edges(a, b);
+        debug_assert!(self.is_cycle(), "not a cycle after: {:?}", (a, b));
+    }
+
+    /// Same as `twist`, but checks that the edges are in the path and that the result is still a cycle.
+    /// The path is left unchanged on error.
+    pub fn try_twist(&mut self, (a0, a1): (usize, usize), (b0, b1): (usize, usize)) -> Result<(), PathError> {
+        for &(v0, v1) in [(a0, a1), (b0, b1)].iter() {
+            if !self.has_edge((v0, v1)) || v0 == v1 { return Err(PathError::NotAnEdge(v0, v1)); }
+        }
+
+        let mut vertices = [a0, a1, b0, b1];
+        vertices.sort_unstable();
+        if let Some(w) = vertices.windows(2).find(|w| w[0] == w[1]) {
+            return Err(PathError::Repeated(w[0]));
+        }
+
+        self.swap_edges((a0, a1), (b0, b1));
+
+        // Edges in opposite directions split the path in two.
+        match self.check_hamiltonian_fast() {
+            HamiltonianResult::Ok => Ok(()),
+            result => {
+                self.swap_edges((a0, b0), (a1, b1));
+                Err(PathError::NotHamiltonian(result))
+            }
+        }
+    }
+
+    #[inline]
+    fn swap_edges(&mut self, (a0, a1): (usize, usize), (b0, b1): (usize, usize)) {
+        let size = self.0.len();
+        assert!(a0 < size && a1 < size && b0 < size && b1 < size);
+
         self.twist_helper(a0, a1, b0);
         self.twist_helper(a1, a0, b1);
         self.twist_helper(b0, b1, a0);
         self.twist_helper(b1, b0, a1);
-        debug_assert!(self.is_hamiltonian(), "not hamiltonian after: {:?}", ((a0, a1), (b0, b1)));
     }
 }
 
 impl Index<usize> for Path {
     type Output = (usize, usize);
 
+    #[inline]
     fn index(&self, index: usize) -> &Self::Output {
-        debug_assert!(index < self.0.len());
-        unsafe { self.0.get_unchecked(index) }
+        &self.0[index]
     }
 }
 
-impl IndexMut<usize> for Path {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        debug_assert!(index < self.0.len());
-        unsafe { self.0.get_unchecked_mut(index) }
+impl Display for PathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            PathError::TooSmall(size) => write!(f, "path needs at least 2 vertices, found {}", size),
+            PathError::OutOfRange(v) => write!(f, "vertex {} is out of range", v),
+            PathError::Repeated(v) => write!(f, "vertex {} is repeated", v),
+            PathError::NotAnEdge(v0, v1) => write!(f, "({}, {}) is not an edge of the path", v0, v1),
+            PathError::NotHamiltonian(result) => write!(f, "path is not hamiltonian: {:?}", result),
+        }
     }
 }
 
+impl Error for PathError {}
+
 impl Display for Path {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "Path({:?})", self.vertices_visited().collect::<Vec<_>>())
@@ -227,6 +432,41 @@ mod tests {
     }
 
 
+    #[cfg(test)]
+    mod checked {
+        use crate::path::{Path, PathError, HamiltonianResult};
+
+        #[test]
+        fn new() {
+            assert_eq!(Path::try_new(vec![(1, 4), (0, 3), (3, 4), (1, 2), (0, 2)]), Ok(super::get_path()));
+            assert_eq!(Path::try_new(vec![(1, 1)]), Err(PathError::TooSmall(1)));
+            assert_eq!(Path::try_new(vec![(1, 4), (0, 3), (3, 9), (1, 2), (0, 2)]), Err(PathError::OutOfRange(9)));
+            assert_eq!(Path::try_new(vec![(1, 2), (0, 2), (0, 1), (4, 4), (3, 3)]), Err(PathError::NotHamiltonian(HamiltonianResult::NotVisited(3))));
+        }
+
+        #[test]
+        fn from_order() {
+            assert_eq!(Path::try_from_order(&[0, 1, 3, 2, 4]), Ok(super::get_path()));
+            assert_eq!(Path::try_from_order(&[0, 1, 3, 2, 5]), Err(PathError::OutOfRange(5)));
+            assert_eq!(Path::try_from_order(&[0, 1, 3, 1, 4]), Err(PathError::Repeated(1)));
+        }
+
+        #[test]
+        fn twist() {
+            let mut path = super::get_path();
+
+            assert_eq!(path.try_twist((0, 2), (3, 4)), Err(PathError::NotAnEdge(0, 2)));
+            assert_eq!(path.try_twist((0, 1), (1, 3)), Err(PathError::Repeated(1)));
+
+            // Edges in opposite directions.
+            assert!(matches!(path.try_twist((0, 1), (2, 3)), Err(PathError::NotHamiltonian(_))));
+            assert_eq!(path.vertices_visited().collect::<Vec<_>>(), vec![1, 3, 2, 4, 0]);
+
+            assert_eq!(path.try_twist((0, 1), (3, 2)), Ok(()));
+            assert!(path.is_hamiltonian());
+        }
+    }
+
     #[cfg(test)]
     mod hamiltonian {
         use crate::path::{Path, HamiltonianResult};
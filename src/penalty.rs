@@ -0,0 +1,165 @@
+use crate::matrix::{EdgeWeights, SymmetricMatrix};
+use crate::weight::Weight;
+
+/// Penalties of the guided local search. Only the penalized edges are stored,
+/// as a list per vertex sorted by the other end of the edge.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Penalties {
+    adjacent: Vec<Vec<(usize, u32)>>,
+}
+
+impl Penalties {
+    pub fn new(size: usize) -> Self {
+        Self { adjacent: vec![vec![]; size] }
+    }
+
+    pub fn size(&self) -> usize {
+        self.adjacent.len()
+    }
+
+    /// Number of penalized edges.
+    pub fn len(&self) -> usize {
+        self.adjacent.iter().map(|a| a.len()).sum::<usize>() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.adjacent.iter().all(|a| a.is_empty())
+    }
+
+    #[inline]
+    pub fn get(&self, (i, j): (usize, usize)) -> u32 {
+        // Searching the shorter list keeps lookups cheap around heavily penalized vertices.
+        let (i, j) = if self.adjacent[i].len() <= self.adjacent[j].len() { (i, j) } else { (j, i) };
+        match self.adjacent[i].binary_search_by_key(&j, |&(v, _)| v) {
+            Ok(k) => self.adjacent[i][k].1,
+            Err(_) => 0,
+        }
+    }
+
+    /// Sets the penalty of an edge. A penalty of zero removes it.
+    pub fn set(&mut self, (i, j): (usize, usize), penalty: u32) {
+        assert!(i != j && i < self.size() && j < self.size(), "not an edge: {:?}", (i, j));
+
+        for &(v0, v1) in [(i, j), (j, i)].iter() {
+            let adjacent = &mut self.adjacent[v0];
+            match (adjacent.binary_search_by_key(&v1, |&(v, _)| v), penalty) {
+                (Ok(k), 0) => { adjacent.remove(k); }
+                (Ok(k), _) => adjacent[k].1 = penalty,
+                (Err(_), 0) => {}
+                (Err(k), _) => adjacent.insert(k, (v1, penalty)),
+            }
+        }
+    }
+
+    #[inline]
+    pub fn inc(&mut self, index: (usize, usize), value: u32) -> u32 {
+        let value = self.get(index) + value;
+        self.set(index, value);
+        value
+    }
+
+    /// Multiplies every penalty by the factor, rounding down. The ones that reach zero are removed.
+    pub fn scale(&mut self, factor: f64) {
+        for adjacent in self.adjacent.iter_mut() {
+            for (_, penalty) in adjacent.iter_mut() {
+                *penalty = (*penalty as f64 * factor) as u32;
+            }
+            adjacent.retain(|&(_, p)| p > 0);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for adjacent in self.adjacent.iter_mut() {
+            adjacent.clear();
+        }
+    }
+
+    /// Penalized edges `(i, j)` with `i < j`, in order.
+    pub fn penalized(&self) -> impl Iterator<Item=((usize, usize), u32)> + '_ {
+        self.adjacent.iter().enumerate().flat_map(|(i, adjacent)| {
+            adjacent.iter().filter(move |&&(j, _)| i < j).map(move |&(j, p)| ((i, j), p))
+        })
+    }
+}
+
+/// Weights plus penalties, computed on the fly instead of kept in a second matrix.
+pub struct Augmented<'a, W> {
+    tsp: &'a SymmetricMatrix<W>,
+    penalties: &'a Penalties,
+    factor: W,
+}
+
+impl<'a, W: Weight> Augmented<'a, W> {
+    /// Each unit of penalty adds `factor` to the weight of the edge.
+    pub fn new(tsp: &'a SymmetricMatrix<W>, penalties: &'a Penalties, factor: W) -> Self {
+        assert_eq!(tsp.size(), penalties.size());
+        Self { tsp, penalties, factor }
+    }
+
+    #[inline]
+    fn augment(&self, weight: W, edge: (usize, usize)) -> W {
+        match self.penalties.get(edge) {
+            0 => weight,
+            penalty => weight + self.factor * W::from_f64(penalty as f64),
+        }
+    }
+}
+
+impl<W: Weight> EdgeWeights<W> for Augmented<'_, W> {
+    fn size(&self) -> usize {
+        self.tsp.size()
+    }
+
+    #[inline]
+    fn weight(&self, edge: (usize, usize)) -> W {
+        self.augment(self.tsp[edge], edge)
+    }
+
+    #[inline]
+    unsafe fn weight_unchecked(&self, edge: (usize, usize)) -> W {
+        self.augment(self.tsp.get_unchecked(edge), edge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::{EdgeWeights, SymmetricMatrix};
+    use crate::penalty::{Augmented, Penalties};
+
+    #[test]
+    fn sparse() {
+        let mut penalties = Penalties::new(5);
+        assert!(penalties.is_empty());
+
+        assert_eq!(penalties.inc((3, 1), 1), 1);
+        assert_eq!(penalties.inc((1, 3), 1), 2);
+        penalties.set((4, 0), 5);
+        penalties.set((1, 0), 1);
+
+        assert_eq!(penalties.get((3, 1)), 2);
+        assert_eq!(penalties.get((0, 4)), 5);
+        assert_eq!(penalties.get((2, 4)), 0);
+        assert_eq!(penalties.len(), 3);
+        assert_eq!(penalties.penalized().collect::<Vec<_>>(), vec![((0, 1), 1), ((0, 4), 5), ((1, 3), 2)]);
+
+        penalties.set((0, 4), 0);
+        assert_eq!(penalties.get((4, 0)), 0);
+        assert_eq!(penalties.len(), 2);
+
+        penalties.scale(0.5);
+        assert_eq!(penalties.penalized().collect::<Vec<_>>(), vec![((1, 3), 1)]);
+        penalties.clear();
+        assert!(penalties.is_empty());
+    }
+
+    #[test]
+    fn augmented() {
+        let tsp = SymmetricMatrix::<u32>::from_euc_2d(&[(0, 0), (3, 4), (6, 0)]);
+        let mut penalties = Penalties::new(3);
+        penalties.set((0, 1), 2);
+
+        let augmented = Augmented::new(&tsp, &penalties, 10);
+        assert_eq!(augmented.weight((1, 0)), 25);
+        assert_eq!(augmented.weight((1, 2)), 5);
+    }
+}
@@ -0,0 +1,185 @@
+use std::cmp::Reverse;
+use std::fmt::{Display, Formatter};
+use std::fmt;
+use crate::matrix::SymmetricMatrix;
+use crate::path::Path;
+use crate::route::Route;
+use crate::local_search_step;
+
+/// How prizes and length are traded off.
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum Objective {
+    /// Orienteering: maximize the prize collected with a tour not longer than the budget.
+    Budget(u32),
+    /// Prize-collecting: minimize the length of the tour minus the prize collected.
+    Penalty,
+}
+
+impl Objective {
+    /// Smaller is better. Tours over budget are always worse than the ones inside it.
+    fn score(self, cost: u32, prize: u32) -> (u32, i64, u32) {
+        match self {
+            Objective::Budget(budget) => (cost.saturating_sub(budget), -(prize as i64), cost),
+            Objective::Penalty => (0, cost as i64 - prize as i64, 0),
+        }
+    }
+}
+
+/// Tour over a subset of the vertices. The vertex 0 is the depot and is always visited.
+#[derive(Eq, PartialEq, Debug)]
+pub struct PrizeRoute {
+    pub route: Route,
+    pub prize: u32,
+}
+
+impl PrizeRoute {
+    /// Route visiting only the depot.
+    pub fn depot(prizes: &[u32]) -> Self {
+        Self { route: Route::new(0, Path::singleton(prizes.len())), prize: prizes[0] }
+    }
+
+    pub fn is_visited(&self, vertex: usize) -> bool {
+        self.route.path.contains(vertex)
+    }
+
+    /// Vertices visited by the route in order, ending in 0.
+    pub fn visited(&self) -> Vec<usize> {
+        self.route.path.vertices_visited().collect()
+    }
+
+    fn score(&self, objective: Objective) -> (u32, i64, u32) {
+        objective.score(self.route.cost, self.prize)
+    }
+}
+
+impl Display for PrizeRoute {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "PrizeRoute {{ cost: {}, prize: {}, {} }}", self.route.cost, self.prize, self.route.path)
+    }
+}
+
+/// Applies 2-opt moves until the visited vertices are on a local minimum.
+fn two_opt(tsp: &SymmetricMatrix, candidate: &mut PrizeRoute, edge_buffer: &mut Vec<(usize, usize)>) {
+    while let Some((a, b)) = {
+        candidate.route.path.edges_visited_buffered(edge_buffer);
+        local_search_step(tsp, edge_buffer, None)
+    } {
+        candidate.route.twist(tsp, a, b);
+    }
+}
+
+/// Inserts the unvisited vertex that improves the objective the most in its cheapest position.
+fn insert(tsp: &SymmetricMatrix, prizes: &[u32], objective: Objective, candidate: &mut PrizeRoute, edge_buffer: &[(usize, usize)]) -> bool {
+    let best = (1..prizes.len())
+        .filter(|&v| !candidate.is_visited(v))
+        .map(|v| {
+            let (cost, edge) = edge_buffer.iter().copied()
+                .map(|(e0, e1)| (candidate.route.cost + tsp[(e0, v)] + tsp[(v, e1)] - tsp[(e0, e1)], (e0, e1)))
+                .min_by_key(|&(cost, _)| cost)
+                .unwrap();
+
+            let score = objective.score(cost, candidate.prize + prizes[v]);
+            (score, v, edge)
+        })
+        .min_by_key(|&(score, ..)| score);
+
+    match best {
+        Some((score, v, edge)) if score < candidate.score(objective) => {
+            candidate.route.insert_vertex(tsp, v, edge);
+            candidate.prize += prizes[v];
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Removes the visited vertex that improves the objective the most.
+fn remove(tsp: &SymmetricMatrix, prizes: &[u32], objective: Objective, candidate: &mut PrizeRoute) -> bool {
+    let best = (1..prizes.len())
+        .filter(|&v| candidate.is_visited(v))
+        .map(|v| {
+            let (v0, v1) = candidate.route.path[v];
+            let cost = candidate.route.cost + tsp[(v0, v1)] - tsp[(v0, v)] - tsp[(v, v1)];
+
+            let score = objective.score(cost, candidate.prize - prizes[v]);
+            (score, Reverse(prizes[v]), v)
+        })
+        .min_by_key(|&(score, prize, ..)| (score, prize));
+
+    match best {
+        Some((score, _, v)) if score < candidate.score(objective) => {
+            candidate.route.drop_vertex(tsp, v);
+            candidate.prize -= prizes[v];
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Local search with 2-opt, add and drop moves.
+pub fn local_search(tsp: &SymmetricMatrix, prizes: &[u32], objective: Objective, candidate: &mut PrizeRoute, edge_buffer: &mut Vec<(usize, usize)>) {
+    loop {
+        two_opt(tsp, candidate, edge_buffer);
+
+        if insert(tsp, prizes, objective, candidate, edge_buffer) { continue; }
+        if remove(tsp, prizes, objective, candidate) { continue; }
+        break;
+    }
+
+    debug_assert_eq!(candidate.route.cost, tsp.cost(&candidate.route.path));
+}
+
+/// Solves the prize-collecting or orienteering problem starting from the depot alone.
+pub fn prize_collecting(tsp: &SymmetricMatrix, prizes: &[u32], objective: Objective) -> PrizeRoute {
+    assert_eq!(tsp.size(), prizes.len());
+
+    let mut route = PrizeRoute::depot(prizes);
+    let mut edge_buffer = Vec::with_capacity(tsp.size());
+    local_search(tsp, prizes, objective, &mut route, &mut edge_buffer);
+
+    debug_assert!(route.route.path.is_cycle());
+    route
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::SymmetricMatrix;
+    use crate::prize::{prize_collecting, Objective};
+
+    fn matrix() -> SymmetricMatrix {
+        SymmetricMatrix::from_euc_2d(&[
+            (0, 0), (10, 0), (10, 10), (0, 10), (100, 100),
+        ])
+    }
+
+    #[test]
+    fn budget() {
+        let tsp = matrix();
+        let prizes = [0, 5, 5, 5, 100];
+
+        // The far vertex doesn't fit the budget.
+        let route = prize_collecting(&tsp, &prizes, Objective::Budget(50));
+        assert_eq!(route.route.cost, 40);
+        assert_eq!(route.prize, 15);
+        assert!(!route.is_visited(4));
+
+        let route = prize_collecting(&tsp, &prizes, Objective::Budget(300));
+        assert_eq!(route.prize, 115);
+        assert!(route.route.path.is_hamiltonian());
+    }
+
+    #[test]
+    fn penalty() {
+        let tsp = matrix();
+
+        // Only the close vertices are worth their detour.
+        let prizes = [0, 30, 30, 30, 100];
+        let route = prize_collecting(&tsp, &prizes, Objective::Penalty);
+        assert_eq!(route.route.cost, 40);
+        assert_eq!(route.prize, 90);
+
+        let mut visited = route.visited();
+        visited.sort();
+        assert_eq!(visited, vec![0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,279 @@
+use std::fmt::Write;
+use std::fs;
+use crate::path::Path;
+use crate::penalty::Penalties;
+
+/// What to draw besides the nodes and the tour.
+#[derive(PartialEq, Debug, Clone)]
+pub struct RenderOptions {
+    /// Size in pixels of the longest side of the image.
+    pub size: u32,
+    pub margin: u32,
+    pub node_radius: f64,
+    /// Edges penalized by the guided local search, drawn in red whether they are on the tour or not.
+    pub penalized: Vec<(usize, usize)>,
+    /// Edges that must be on the tour, drawn in blue whether they are on the tour or not.
+    pub fixed: Vec<(usize, usize)>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self { size: 1000, margin: 10, node_radius: 2.0, penalized: vec![], fixed: vec![] }
+    }
+}
+
+/// Maps the coordinates of the instance to pixels, with the y axis pointing up.
+pub(crate) struct Frame {
+    min: (f64, f64),
+    max_y: f64,
+    scale: f64,
+    margin: f64,
+    width: u32,
+    height: u32,
+}
+
+impl Frame {
+    pub(crate) fn new(coords: &[(i32, i32)], options: &RenderOptions) -> Result<Self, String> {
+        if coords.is_empty() {
+            return Err("no coordinates to draw".to_owned());
+        }
+
+        let (mut min, mut max) = ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN));
+        for &(x, y) in coords {
+            let (x, y) = (x as f64, y as f64);
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+
+        let margin = options.margin as f64;
+        let span = (max.0 - min.0).max(max.1 - min.1).max(1.0);
+        let scale = (options.size as f64 - 2.0 * margin).max(1.0) / span;
+
+        let width = ((max.0 - min.0) * scale + 2.0 * margin).ceil() as u32 + 1;
+        let height = ((max.1 - min.1) * scale + 2.0 * margin).ceil() as u32 + 1;
+
+        Ok(Self { min, max_y: max.1, scale, margin, width, height })
+    }
+
+    fn point(&self, (x, y): (i32, i32)) -> (f64, f64) {
+        ((x as f64 - self.min.0) * self.scale + self.margin, (self.max_y - y as f64) * self.scale + self.margin)
+    }
+}
+
+/// Edges with a positive penalty, to be used as `RenderOptions::penalized`.
+pub fn penalized_edges(penalties: &Penalties) -> Vec<(usize, usize)> {
+    penalties.penalized().map(|(edge, _)| edge).collect()
+}
+
+pub(crate) fn check(coords: &[(i32, i32)], path: &Path, options: &RenderOptions) -> Result<(), String> {
+    if coords.len() != path.size() {
+        return Err(format!("{} coordinates for a path over {} vertices", coords.len(), path.size()));
+    }
+    match options.penalized.iter().chain(options.fixed.iter()).find(|&&(v0, v1)| v0 >= coords.len() || v1 >= coords.len()) {
+        Some(edge) => Err(format!("edge {:?} is out of range", edge)),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn svg_header(res: &mut String, frame: &Frame) {
+    writeln!(res, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+             frame.width, frame.height, frame.width, frame.height).unwrap();
+    writeln!(res, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+}
+
+/// The tour and the highlighted edges.
+pub(crate) fn svg_edges(res: &mut String, frame: &Frame, coords: &[(i32, i32)], path: &Path, options: &RenderOptions) {
+    let line = |res: &mut String, (v0, v1): (usize, usize), style: &str| {
+        let (a, b) = (frame.point(coords[v0]), frame.point(coords[v1]));
+        writeln!(res, r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" {}/>"#, a.0, a.1, b.0, b.1, style).unwrap();
+    };
+
+    res.push_str("<g class=\"tour\">\n");
+    for edge in path.edges_visited() {
+        line(res, edge, r#"stroke="black" stroke-width="1""#);
+    }
+    res.push_str("</g>\n<g class=\"penalized\">\n");
+    for &edge in options.penalized.iter() {
+        line(res, edge, r#"stroke="red" stroke-width="1.5" stroke-opacity="0.6""#);
+    }
+    res.push_str("</g>\n<g class=\"fixed\">\n");
+    for &edge in options.fixed.iter() {
+        line(res, edge, r#"stroke="blue" stroke-width="2""#);
+    }
+    res.push_str("</g>\n");
+}
+
+pub(crate) fn svg_nodes(res: &mut String, frame: &Frame, coords: &[(i32, i32)], options: &RenderOptions) {
+    res.push_str("<g class=\"nodes\">\n");
+    for (v, &point) in coords.iter().enumerate() {
+        let (x, y) = frame.point(point);
+        writeln!(res, r#"<circle cx="{:.1}" cy="{:.1}" r="{}"><title>{}</title></circle>"#, x, y, options.node_radius, v).unwrap();
+    }
+    res.push_str("</g>\n");
+}
+
+/// Draws the nodes of a coordinate instance and the tour over them.
+/// Fails if the coordinates are empty or don't match the path, or a highlighted edge is out of range.
+pub fn svg(coords: &[(i32, i32)], path: &Path, options: &RenderOptions) -> Result<String, String> {
+    check(coords, path, options)?;
+    let frame = Frame::new(coords, options)?;
+
+    let mut res = String::new();
+    svg_header(&mut res, &frame);
+    svg_edges(&mut res, &frame, coords, path, options);
+    svg_nodes(&mut res, &frame, coords, options);
+    res.push_str("</svg>\n");
+
+    Ok(res)
+}
+
+pub fn write_svg(file: &str, coords: &[(i32, i32)], path: &Path, options: &RenderOptions) -> Result<(), String> {
+    fs::write(file, svg(coords, path, options)?).map_err(|e| format!("{}: {}", file, e))
+}
+
+/// RGB image drawn with the same layout as `svg`.
+#[cfg(feature = "png")]
+struct Canvas {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+#[cfg(feature = "png")]
+impl Canvas {
+    fn plot(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 { return; }
+        let i = 3 * (y as usize * self.width as usize + x as usize);
+        self.data[i..i + 3].copy_from_slice(&color);
+    }
+
+    /// Bresenham's line, thickened by drawing it shifted around its center.
+    fn line(&mut self, a: (f64, f64), b: (f64, f64), thickness: i64, color: [u8; 3]) {
+        let (x0, y0) = (a.0.round() as i64, a.1.round() as i64);
+        let (x1, y1) = (b.0.round() as i64, b.1.round() as i64);
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+
+        let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+        loop {
+            for ox in 0..thickness {
+                for oy in 0..thickness {
+                    self.plot(x + ox - thickness / 2, y + oy - thickness / 2, color);
+                }
+            }
+
+            if x == x1 && y == y1 { break; }
+            let e2 = 2 * err;
+            if e2 >= dy { err += dy; x += sx; }
+            if e2 <= dx { err += dx; y += sy; }
+        }
+    }
+
+    fn dot(&mut self, (x, y): (f64, f64), radius: f64, color: [u8; 3]) {
+        let r = radius.ceil() as i64;
+        let (cx, cy) = (x.round() as i64, y.round() as i64);
+        for ox in -r..=r {
+            for oy in -r..=r {
+                if ((ox * ox + oy * oy) as f64) <= radius * radius {
+                    self.plot(cx + ox, cy + oy, color);
+                }
+            }
+        }
+    }
+}
+
+/// Same drawing as `svg`, encoded as a PNG.
+#[cfg(feature = "png")]
+pub fn png(coords: &[(i32, i32)], path: &Path, options: &RenderOptions) -> Result<Vec<u8>, String> {
+    check(coords, path, options)?;
+    let frame = Frame::new(coords, options)?;
+
+    let (width, height) = (frame.width, frame.height);
+    let mut canvas = Canvas { width, height, data: vec![255; 3 * width as usize * height as usize] };
+    let point = |v: usize| frame.point(coords[v]);
+
+    for (v0, v1) in path.edges_visited() {
+        canvas.line(point(v0), point(v1), 1, [0, 0, 0]);
+    }
+    for &(v0, v1) in options.penalized.iter() {
+        canvas.line(point(v0), point(v1), 2, [220, 40, 40]);
+    }
+    for &(v0, v1) in options.fixed.iter() {
+        canvas.line(point(v0), point(v1), 2, [40, 40, 220]);
+    }
+    for v in 0..coords.len() {
+        canvas.dot(point(v), options.node_radius, [0, 0, 0]);
+    }
+
+    let mut res = vec![];
+    let mut encoder = png::Encoder::new(&mut res, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()
+        .and_then(|mut writer| writer.write_image_data(&canvas.data))
+        .map_err(|e| e.to_string())?;
+
+    Ok(res)
+}
+
+#[cfg(feature = "png")]
+pub fn write_png(file: &str, coords: &[(i32, i32)], path: &Path, options: &RenderOptions) -> Result<(), String> {
+    fs::write(file, png(coords, path, options)?).map_err(|e| format!("{}: {}", file, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::Path;
+    use crate::penalty::Penalties;
+    use crate::render::{penalized_edges, svg, RenderOptions};
+
+    const COORDS: [(i32, i32); 4] = [(0, 0), (100, 0), (100, 50), (0, 50)];
+
+    #[test]
+    fn layout() {
+        let path = Path::from_order(&[0, 1, 2, 3]);
+        let options = RenderOptions { size: 220, margin: 10, ..RenderOptions::default() };
+        let svg = svg(&COORDS, &path, &options).unwrap();
+
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="221" height="121""#));
+        assert_eq!(svg.matches("<line").count(), 4);
+        assert_eq!(svg.matches("<circle").count(), 4);
+
+        // The y axis points up.
+        assert!(svg.contains(r#"<circle cx="10.0" cy="110.0""#));
+        assert!(svg.contains(r#"<circle cx="210.0" cy="10.0""#));
+    }
+
+    #[test]
+    fn highlights() {
+        let mut penalties = Penalties::new(4);
+        penalties.set((1, 2), 3);
+        penalties.set((3, 0), 1);
+        assert_eq!(penalized_edges(&penalties), vec![(0, 3), (1, 2)]);
+
+        let path = Path::from_order(&[0, 1, 2, 3]);
+        let options = RenderOptions { penalized: penalized_edges(&penalties), fixed: vec![(0, 2)], ..RenderOptions::default() };
+        let svg = svg(&COORDS, &path, &options).unwrap();
+
+        assert_eq!(svg.matches(r#"stroke="red""#).count(), 2);
+        assert_eq!(svg.matches(r#"stroke="blue""#).count(), 1);
+    }
+
+    #[test]
+    fn invalid() {
+        let path = Path::from_order(&[0, 1, 2, 3]);
+        let options = RenderOptions { fixed: vec![(0, 4)], ..RenderOptions::default() };
+
+        assert_eq!(svg(&COORDS, &path, &options).err().unwrap(), "edge (0, 4) is out of range");
+        assert_eq!(svg(&COORDS[..3], &path, &RenderOptions::default()).err().unwrap(), "3 coordinates for a path over 4 vertices");
+        assert_eq!(svg(&[], &path, &RenderOptions::default()).err().unwrap(), "0 coordinates for a path over 4 vertices");
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png() {
+        let path = Path::from_order(&[0, 1, 2, 3]);
+        let png = crate::render::png(&COORDS, &path, &RenderOptions::default()).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+}
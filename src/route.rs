@@ -1,22 +1,81 @@
+use crate::matrix::SymmetricMatrix;
 use crate::path::Path;
+use crate::weight::Weight;
 use std::fmt::{Display, Formatter};
 use std::fmt;
 
-#[derive(Eq, PartialEq, Debug)]
-pub struct Route {
-    pub cost: u32,
+#[derive(Eq, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub struct Route<W = u32> {
+    pub cost: W,
     pub path: Path,
 }
 
-impl Route
+impl<W: Weight> Route<W>
 {
-    pub fn new(cost: u32, path: Path) -> Route {
+    pub fn new(cost: W, path: Path) -> Self {
         Route { cost, path }
     }
+
+    /// Twists two edges of the path keeping the cost up to date. See `Path::twist`.
+    #[inline]
+    pub fn twist(&mut self, tsp: &SymmetricMatrix<W>, (a0, a1): (usize, usize), (b0, b1): (usize, usize)) {
+        self.cost = self.cost + tsp[(a0, b0)] + tsp[(a1, b1)] - tsp[(a0, a1)] - tsp[(b0, b1)];
+        self.path.twist((a0, a1), (b0, b1));
+        self.debug_check_cost(tsp);
+    }
+
+    /// Removes a vertex from the path keeping the cost up to date. See `Path::drop_vertex`.
+    pub fn drop_vertex(&mut self, tsp: &SymmetricMatrix<W>, vertex: usize) {
+        let (v0, v1) = self.path[vertex];
+        self.cost = self.cost + tsp[(v0, v1)] - tsp[(v0, vertex)] - tsp[(vertex, v1)];
+        self.path.drop_vertex(vertex);
+        self.debug_check_cost(tsp);
+    }
+
+    /// Inserts a vertex in the path keeping the cost up to date. See `Path::insert_vertex`.
+    pub fn insert_vertex(&mut self, tsp: &SymmetricMatrix<W>, vertex: usize, (v0, v1): (usize, usize)) {
+        self.cost = self.cost + tsp[(v0, vertex)] + tsp[(vertex, v1)] - tsp[(v0, v1)];
+        self.path.insert_vertex(vertex, (v0, v1));
+        self.debug_check_cost(tsp);
+    }
+
+    /// Whether the cost matches the one computed from scratch, up to rounding for real weights.
+    pub fn check_cost(&self, tsp: &SymmetricMatrix<W>) -> bool {
+        self.cost.approx_eq(tsp.cost(&self.path))
+    }
+
+    #[inline]
+    fn debug_check_cost(&self, tsp: &SymmetricMatrix<W>) {
+        debug_assert!(self.check_cost(tsp), "incremental cost {} diverged from {}", self.cost, self.path);
+    }
 }
 
-impl Display for Route {
+impl<W: Weight> Display for Route<W> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "Route {{ cost: {}, {} }}", self.cost, self.path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::SymmetricMatrix;
+    use crate::path::Path;
+    use crate::route::Route;
+
+    #[test]
+    fn incremental_cost() {
+        let tsp: SymmetricMatrix = SymmetricMatrix::from_euc_2d(&[(0, 0), (10, 0), (0, 10), (10, 10), (5, 20)]);
+        let path = Path::from_order(&[0, 1, 2, 3, 4]);
+        let mut route = Route::new(tsp.cost(&path), path);
+
+        route.twist(&tsp, (0, 1), (2, 3));
+        assert_eq!(route.cost, tsp.cost(&route.path));
+
+        route.drop_vertex(&tsp, 4);
+        assert_eq!(route.cost, 48);
+
+        route.insert_vertex(&tsp, 4, (3, route.path[3].0));
+        assert!(route.check_cost(&tsp));
+    }
+}
@@ -0,0 +1,340 @@
+use crate::matrix::AsymmetricMatrix;
+use crate::path::Path;
+
+/// Precedence constraints: some vertices must be visited before others on a tour starting at 0.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Precedences {
+    before: Vec<Vec<usize>>,
+    after: Vec<Vec<usize>>,
+}
+
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum PrecedenceResult {
+    Ok,
+    /// The first vertex must be visited before the second, but it isn't.
+    Violated(usize, usize),
+}
+
+/// Tour with a direction. It starts at the vertex 0 and goes back to it after the last vertex.
+/// Its cost follows the direction, so the weights may be asymmetric.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct OrientedRoute {
+    pub cost: u32,
+    pub tour: Vec<usize>,
+}
+
+impl OrientedRoute {
+    pub fn new(tsp: &AsymmetricMatrix, tour: Vec<usize>) -> Self {
+        let cost = tour_cost(tsp, &tour);
+        Self { cost, tour }
+    }
+
+    /// Orients a path in the direction of `Path::vertices_visited`.
+    pub fn from_path(tsp: &AsymmetricMatrix, path: &Path) -> Self {
+        Self::new(tsp, orient(path))
+    }
+
+    pub fn path(&self) -> Path {
+        Path::from_order(&self.tour)
+    }
+}
+
+/// Vertices of the path from 0, in the direction of `Path::vertices_visited`.
+pub(crate) fn orient(path: &Path) -> Vec<usize> {
+    let tour = path.vertices_visited();
+    Some(0).into_iter().chain(tour.take_while(|&v| v != 0)).collect()
+}
+
+fn tour_cost(tsp: &AsymmetricMatrix, tour: &[usize]) -> u32 {
+    (0..tour.len())
+        .map(|i| tsp[(tour[i], tour[(i + 1) % tour.len()])])
+        .sum()
+}
+
+impl Precedences {
+    /// Each pair `(a, b)` means that `a` must be visited before `b`.
+    pub fn new(size: usize, pairs: &[(usize, usize)]) -> Self {
+        Self::try_new(size, pairs).unwrap()
+    }
+
+    /// Same as `new`, but reports pairs out of range or from a vertex to itself instead of panicking.
+    pub fn try_new(size: usize, pairs: &[(usize, usize)]) -> Result<Self, String> {
+        let mut before = vec![vec![]; size];
+        let mut after = vec![vec![]; size];
+
+        for &(a, b) in pairs {
+            if a >= size || b >= size || a == b {
+                return Err(format!("invalid precedence {} before {}", a, b));
+            }
+            after[a].push(b);
+            before[b].push(a);
+        }
+
+        Ok(Self { before, after })
+    }
+
+    /// Reads the precedences of a TSPLIB SOP file. See `read_sop`.
+    pub fn from_sop(text: &str) -> Result<Self, String> {
+        read_sop(text).map(|(_, precedences)| precedences)
+    }
+
+    pub fn size(&self) -> usize {
+        self.before.len()
+    }
+
+    /// Vertices that must be visited before the given one.
+    pub fn before(&self, vertex: usize) -> &[usize] {
+        &self.before[vertex]
+    }
+
+    /// Vertices that must be visited after the given one.
+    pub fn after(&self, vertex: usize) -> &[usize] {
+        &self.after[vertex]
+    }
+
+    /// Check if the tour respects all precedences, reporting the first violation in the order of the tour.
+    pub fn check(&self, tour: &[usize]) -> PrecedenceResult {
+        debug_assert_eq!(tour.len(), self.size());
+
+        let mut visited = vec![false; self.size()];
+
+        for &vertex in tour {
+            if let Some(&missing) = self.before[vertex].iter().find(|&&b| !visited[b]) {
+                return PrecedenceResult::Violated(missing, vertex);
+            }
+            visited[vertex] = true;
+        }
+
+        PrecedenceResult::Ok
+    }
+
+    pub fn is_feasible(&self, tour: &[usize]) -> bool {
+        self.check(tour) == PrecedenceResult::Ok
+    }
+
+    /// Feasible tour choosing the nearest available vertex at each step.
+    /// Returns `None` if the precedences have a cycle or something must be visited before 0.
+    pub fn nearest_neighbor(&self, tsp: &AsymmetricMatrix) -> Option<OrientedRoute> {
+        let size = self.size();
+        if !self.before[0].is_empty() { return None; }
+
+        let mut missing: Vec<_> = self.before.iter().map(|b| b.len()).collect();
+        for &next in self.after[0].iter() {
+            missing[next] -= 1;
+        }
+        let mut available: Vec<_> = (1..size).filter(|&v| missing[v] == 0).collect();
+
+        let mut tour = vec![0];
+        while let Some(i) = (0..available.len()).min_by_key(|&i| tsp[(tour[tour.len() - 1], available[i])]) {
+            let vertex = available.swap_remove(i);
+            tour.push(vertex);
+
+            for &next in self.after[vertex].iter() {
+                missing[next] -= 1;
+                if missing[next] == 0 { available.push(next); }
+            }
+        }
+
+        if tour.len() < size { return None; }
+        Some(OrientedRoute::new(tsp, tour))
+    }
+}
+
+/// Reads the weights and precedences of a TSPLIB SOP file. An entry -1 on the row `i` and column `j`
+/// of the `EDGE_WEIGHT_SECTION` means `j` must be visited before `i`, any other entry is the weight from `i` to `j`.
+/// Going against a precedence costs nothing, so going back from the last vertex to 0 is free.
+pub fn read_sop(text: &str) -> Result<(AsymmetricMatrix, Precedences), String> {
+    let mut lines = text.lines().enumerate().map(|(n, l)| (n + 1, l.trim()));
+
+    lines.by_ref()
+        .find(|(_, l)| l.starts_with("EDGE_WEIGHT_SECTION"))
+        .ok_or("missing EDGE_WEIGHT_SECTION")?;
+
+    let mut tokens = lines
+        .take_while(|(_, l)| *l != "EOF")
+        .flat_map(|(n, l)| l.split_whitespace().map(move |t| (n, t)))
+        .map(|(n, t)| t.parse::<i64>().map_err(|e| format!("line {}: {}", n, e)));
+
+    let size = tokens.next().ok_or("missing dimension")??;
+    if size < 2 {
+        return Err(format!("invalid dimension {}", size));
+    }
+    let size = size as usize;
+    let count = size.checked_mul(size).ok_or_else(|| format!("invalid dimension {}", size))?;
+
+    // Weights are checked before the matrix is allocated, the dimension may be anything.
+    let mut weights = vec![];
+    let mut pairs = vec![];
+    for k in 0..count {
+        let weight = tokens.next().ok_or_else(|| format!("expected {} weights, found {}", count, k))??;
+        let (i, j) = (k / size, k % size);
+        match weight {
+            -1 => pairs.push((j, i)),
+            0..=0xFFFF_FFFF => {}
+            _ => return Err(format!("invalid weight {} on row {} and column {}", weight, i, j)),
+        }
+        weights.push(weight.max(0) as u32);
+    }
+
+    let mut tsp = AsymmetricMatrix::from_size(size);
+    for (k, weight) in weights.into_iter().enumerate() {
+        tsp.set((k / size, k % size), weight);
+    }
+
+    Ok((tsp, Precedences::try_new(size, &pairs)?))
+}
+
+/// Finds an improving path-preserving 3-opt move, swapping two adjacent segments without reversing them.
+/// Lexicographic search: the left segment grows one vertex at a time, marking the vertices that must come after it,
+/// so the right segment stops growing as soon as it reaches one of them.
+fn three_opt_step(tsp: &AsymmetricMatrix, precedences: &Precedences, route: &mut OrientedRoute, marks: &mut [usize], stamp: &mut usize) -> bool {
+    let tour = &mut route.tour;
+    let size = tour.len();
+
+    if size < 3 { return false; }
+
+    for h in 0..size - 2 {
+        *stamp += 1;
+
+        for i in h + 1..size - 1 {
+            for &next in precedences.after(tour[i]) {
+                marks[next] = *stamp;
+            }
+
+            for j in i + 1..size {
+                if marks[tour[j]] == *stamp { break; }
+
+                let (h0, h1) = (tour[h], tour[h + 1]);
+                let (i0, i1) = (tour[i], tour[i + 1]);
+                let (j0, j1) = (tour[j], tour[(j + 1) % size]);
+
+                let removed = tsp[(h0, h1)] + tsp[(i0, i1)] + tsp[(j0, j1)];
+                let added = tsp[(h0, i1)] + tsp[(j0, h1)] + tsp[(i0, j1)];
+
+                if added < removed {
+                    tour[h + 1..=j].rotate_left(i - h);
+                    route.cost -= removed - added;
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Local search that keeps the tour feasible for the precedences.
+pub fn local_search(tsp: &AsymmetricMatrix, precedences: &Precedences, candidate: &mut OrientedRoute) {
+    debug_assert!(precedences.is_feasible(&candidate.tour));
+
+    let mut marks = vec![0; precedences.size()];
+    let mut stamp = 0;
+    while three_opt_step(tsp, precedences, candidate, &mut marks, &mut stamp) {}
+
+    debug_assert!(precedences.is_feasible(&candidate.tour));
+    debug_assert_eq!(candidate.cost, tour_cost(tsp, &candidate.tour));
+}
+
+/// Solves the sequential ordering problem. Returns `None` if there is no feasible tour.
+pub fn sop(tsp: &AsymmetricMatrix, precedences: &Precedences) -> Option<OrientedRoute> {
+    let mut route = precedences.nearest_neighbor(tsp)?;
+    local_search(tsp, precedences, &mut route);
+    Some(route)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::{AsymmetricMatrix, SymmetricMatrix};
+    use crate::sop::{Precedences, PrecedenceResult, read_sop, sop};
+
+    fn matrix() -> AsymmetricMatrix {
+        AsymmetricMatrix::from(&SymmetricMatrix::from_euc_2d(&[
+            (0, 0), (10, 0), (20, 0), (30, 0), (20, 10), (10, 10),
+        ]))
+    }
+
+    #[test]
+    fn check() {
+        let precedences = Precedences::new(6, &[(3, 1), (2, 5)]);
+
+        assert_eq!(precedences.check(&[0, 3, 1, 2, 4, 5]), PrecedenceResult::Ok);
+        assert_eq!(precedences.check(&[0, 1, 2, 3, 4, 5]), PrecedenceResult::Violated(3, 1));
+        assert_eq!(precedences.check(&[0, 3, 1, 5, 4, 2]), PrecedenceResult::Violated(2, 5));
+    }
+
+    #[test]
+    fn from_sop() {
+        let text = "\
+NAME: test
+TYPE: SOP
+DIMENSION: 3
+EDGE_WEIGHT_TYPE: EXPLICIT
+EDGE_WEIGHT_FORMAT: FULL_MATRIX
+EDGE_WEIGHT_SECTION
+3
+0 1 2
+-1 0 5
+-1 -1 0
+EOF
+";
+        let precedences = Precedences::from_sop(text).unwrap();
+        assert_eq!(precedences.before(2), &[0, 1]);
+        assert_eq!(precedences.after(0), &[1, 2]);
+
+        let (tsp, _) = read_sop(text).unwrap();
+        assert_eq!((tsp[(0, 1)], tsp[(0, 2)], tsp[(1, 2)]), (1, 2, 5));
+        assert_eq!((tsp[(1, 0)], tsp[(2, 0)], tsp[(2, 1)]), (0, 0, 0));
+
+        assert_eq!(Precedences::from_sop("EDGE_WEIGHT_SECTION\n2\n0 x").err().unwrap(), "line 3: invalid digit found in string");
+        assert_eq!(Precedences::from_sop("EDGE_WEIGHT_SECTION\n2\n-1 1 1 0").err().unwrap(), "invalid precedence 0 before 0");
+        assert!(Precedences::from_sop("EDGE_WEIGHT_SECTION\n4294967296\n0").is_err());
+    }
+
+    #[test]
+    fn unconstrained() {
+        let tsp = matrix();
+        let route = sop(&tsp, &Precedences::new(6, &[])).unwrap();
+        assert_eq!(route.cost, 68);
+    }
+
+    #[test]
+    fn constrained() {
+        let tsp = matrix();
+
+        // Go to the far end first, then come back through the middle.
+        let precedences = Precedences::new(6, &[(3, 1), (3, 2)]);
+        let route = sop(&tsp, &precedences).unwrap();
+
+        assert!(precedences.is_feasible(&route.tour));
+        assert_eq!(route.tour[..2], [0, 5]);
+        assert_eq!(route.cost, 68);
+
+        let cyclic = Precedences::new(6, &[(3, 1), (1, 3)]);
+        assert_eq!(sop(&tsp, &cyclic), None);
+
+        let tiny = AsymmetricMatrix::from(&SymmetricMatrix::from_euc_2d(&[(0, 0), (10, 0)]));
+        assert_eq!(sop(&tiny, &Precedences::new(2, &[])).unwrap().cost, 20);
+    }
+
+    #[test]
+    fn asymmetric() {
+        // Going from 1 to 2 costs much more than coming back from 2 to 1.
+        let text = "\
+TYPE: SOP
+DIMENSION: 4
+EDGE_WEIGHT_SECTION
+4
+0 1 1 5
+-1 0 10 1
+-1 1 0 10
+-1 -1 -1 0
+EOF
+";
+        let (tsp, precedences) = read_sop(text).unwrap();
+        assert_eq!((tsp[(1, 2)], tsp[(2, 1)]), (10, 1));
+
+        let route = sop(&tsp, &precedences).unwrap();
+        assert_eq!(route.tour, [0, 2, 1, 3]);
+        assert_eq!(route.cost, 3);
+    }
+}
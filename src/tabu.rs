@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use rayon::prelude::*;
+use crate::matrix::SymmetricMatrix;
+use crate::route::Route;
+use crate::weight::Weight;
+use crate::local_search;
+
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct TabuConfig {
+    /// Iterations during which the edges removed or added by a move can't be added or removed again.
+    pub tenure: usize,
+    /// Size of the candidate list of each vertex. Only moves adding an edge to a candidate are tried.
+    pub candidates: usize,
+    /// Long term memory: worsening moves cost this much more for each time their new edges were added before.
+    pub frequency: Option<f64>,
+}
+
+impl Default for TabuConfig {
+    fn default() -> Self {
+        Self { tenure: 10, candidates: 8, frequency: None }
+    }
+}
+
+#[inline]
+fn key((v0, v1): (usize, usize)) -> (usize, usize) {
+    (v0.min(v1), v0.max(v1))
+}
+
+/// Short and long term memory of the edges moved.
+#[derive(Default)]
+struct Memory {
+    /// Iteration until which each edge is tabu.
+    tabu: HashMap<(usize, usize), usize>,
+    /// How many times each edge was added.
+    frequency: HashMap<(usize, usize), u32>,
+}
+
+impl Memory {
+    fn is_tabu(&self, edge: (usize, usize), step: usize) -> bool {
+        self.tabu.get(&key(edge)).is_some_and(|&until| until > step)
+    }
+
+    fn count(&self, edge: (usize, usize)) -> f64 {
+        self.frequency.get(&key(edge)).copied().unwrap_or(0) as f64
+    }
+
+    /// The edges removed and added by the twist are tabu for the next `tenure` iterations.
+    fn record(&mut self, (a, b): ((usize, usize), (usize, usize)), step: usize, config: &TabuConfig) {
+        for &e in [a, b, (a.0, b.0), (a.1, b.1)].iter() {
+            self.tabu.insert(key(e), step + 1 + config.tenure);
+        }
+        if config.frequency.is_some() {
+            for &e in [(a.0, b.0), (a.1, b.1)].iter() {
+                *self.frequency.entry(key(e)).or_insert(0) += 1;
+            }
+        }
+    }
+}
+
+/// Best admissible move of the route whose edges are `edges`, `gap` more expensive than the best route.
+/// `position` has the index in the edges of the edge that starts at each vertex.
+#[allow(clippy::too_many_arguments)]
+fn choose<W: Weight>(tsp: &SymmetricMatrix<W>, candidates: &[Vec<usize>], config: &TabuConfig, memory: &Memory, edges: &[(usize, usize)], position: &[usize], step: usize, gap: f64) -> Option<((usize, usize), (usize, usize))> {
+    let size = edges.len();
+
+    // Ties go to the first move found, so runs are deterministic.
+    edges
+        .par_iter()
+        .enumerate()
+        .flat_map_iter(|(i, &(v0, _))| candidates[v0].iter().map(move |&c| (i, position[c])))
+        .filter(|&(i, j)| i.max(j) - i.min(j) >= 2 && !(i.min(j) == 0 && i.max(j) == size - 1))
+        .filter_map(|(i, j)| {
+            let ((a0, a1), (b0, b1)) = (edges[i.min(j)], edges[i.max(j)]);
+            let delta = (tsp[(a0, b0)] + tsp[(a1, b1)]).to_f64() - (tsp[(a0, a1)] + tsp[(b0, b1)]).to_f64();
+
+            let moved = [(a0, a1), (b0, b1), (a0, b0), (a1, b1)];
+            if moved.iter().any(|&e| memory.is_tabu(e, step)) && delta + gap >= 0.0 {
+                return None;
+            }
+
+            let score = match config.frequency {
+                Some(weight) if delta >= 0.0 => delta + weight * (memory.count((a0, b0)) + memory.count((a1, b1))),
+                _ => delta,
+            };
+            Some((score, (i, j), ((a0, a1), (b0, b1))))
+        })
+        .min_by(|x, y| x.0.partial_cmp(&y.0).unwrap().then(x.1.cmp(&y.1)))
+        .map(|(_, _, m)| m)
+}
+
+/// Tabu search over twists, starting from a local minimum of the nearest neighbor route.
+/// Each of the `steps` iterations takes the best admissible move even if it makes the route worse.
+/// A tabu move is admissible only if it leads to a route cheaper than the best one.
+pub fn tabu_search<W: Weight>(tsp: &SymmetricMatrix<W>, steps: usize, config: &TabuConfig) -> Route<W> {
+    let size = tsp.size();
+    let candidates = tsp.candidates(config.candidates);
+
+    let mut edge_buffer = Vec::with_capacity(size);
+    let mut route = tsp.nearest_neighbor();
+    local_search(tsp, &mut route, &mut edge_buffer);
+    let mut best = route.clone();
+
+    let mut memory = Memory::default();
+    let mut position = vec![0; size];
+
+    for step in 0..steps {
+        route.path.edges_visited_buffered(&mut edge_buffer);
+        for (i, &(v0, _)) in edge_buffer.iter().enumerate() {
+            position[v0] = i;
+        }
+
+        let gap = (route.cost - best.cost).to_f64();
+        let (a, b) = match choose(tsp, &candidates, config, &memory, &edge_buffer, &position, step, gap) {
+            Some(m) => m,
+            None => continue,
+        };
+
+        route.twist(tsp, a, b);
+        memory.record((a, b), step, config);
+
+        if route.cost < best.cost {
+            best = route.clone();
+        }
+    }
+
+    local_search(tsp, &mut best, &mut edge_buffer);
+
+    assert!(best.path.is_hamiltonian());
+    debug_assert!(best.check_cost(tsp));
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture::scattered;
+    use crate::local_search;
+    use crate::route::Route;
+    use crate::tabu::{choose, key, Memory, TabuConfig};
+
+    #[test]
+    fn reversal() {
+        let tsp = scattered::<f64>(40);
+        let config = TabuConfig::default();
+        let candidates = tsp.candidates(config.candidates);
+
+        let mut route = tsp.nearest_neighbor();
+        let mut edges = vec![];
+        local_search(&tsp, &mut route, &mut edges);
+        let local_minimum = route.cost;
+
+        let mut position = vec![0; 40];
+        let mut next = |route: &Route<f64>, memory: &Memory, step: usize, best: f64| {
+            route.path.edges_visited_buffered(&mut edges);
+            for (i, &(v0, _)) in edges.iter().enumerate() {
+                position[v0] = i;
+            }
+            choose(&tsp, &candidates, &config, memory, &edges, &position, step, route.cost - best).unwrap()
+        };
+
+        // On a local minimum the best move makes the route worse.
+        let mut memory = Memory::default();
+        let (a, b) = next(&route, &memory, 0, local_minimum);
+        route.twist(&tsp, a, b);
+        memory.record((a, b), 0, &config);
+        assert!(route.cost > local_minimum);
+
+        // Undoing it is the best move, unless its edges are tabu.
+        let mut undo = [key((a.0, b.0)), key((a.1, b.1))];
+        undo.sort();
+        let removes = |(c, d): ((usize, usize), (usize, usize))| {
+            let mut removed = [key(c), key(d)];
+            removed.sort();
+            removed == undo
+        };
+        assert!(removes(next(&route, &Memory::default(), 1, local_minimum)));
+        assert!(!removes(next(&route, &memory, 1, local_minimum)));
+
+        // Unless it leads to a route cheaper than the best one, as it would if the best one were any more expensive.
+        assert!(removes(next(&route, &memory, 1, local_minimum + 1e-6)));
+
+        // Until the tenure is over.
+        assert!(memory.is_tabu(a, config.tenure));
+        assert!(removes(next(&route, &memory, config.tenure + 1, local_minimum)));
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use crate::path::Path;
+use crate::render::{check, svg_edges, svg_header, svg_nodes, Frame, RenderOptions};
+
+/// Receives the changes `gls` makes as it runs. Every method does nothing by default.
+pub trait Observer {
+    /// The route the search starts from.
+    fn start(&mut self, _path: &Path) {}
+
+    /// Edges passed to `Path::twist`.
+    fn twist(&mut self, _a: (usize, usize), _b: (usize, usize)) {}
+
+    /// New penalty of an edge. Zero when the penalties are reset.
+    fn penalty(&mut self, _edge: (usize, usize), _penalty: u32) {}
+
+    /// End of a step of the guided local search.
+    fn step(&mut self) {}
+
+    /// The current route is the best one found so far.
+    fn best(&mut self) {}
+
+    /// The search goes back to the last route reported by `best`.
+    fn restore(&mut self) {}
+}
+
+/// Observes nothing. Used by `gls`, so it costs nothing when there's no trace.
+impl Observer for () {}
+
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "json", serde(rename_all = "snake_case"))]
+pub enum Event {
+    Twist((usize, usize), (usize, usize)),
+    Penalty((usize, usize), u32),
+    Step,
+    Best,
+    Restore,
+}
+
+/// Every change made by a run of `gls`, enough to replay it from the start.
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct Trace {
+    /// Order of the vertices in the initial route.
+    pub initial: Vec<usize>,
+    pub events: Vec<Event>,
+}
+
+impl Observer for Trace {
+    fn start(&mut self, path: &Path) {
+        let order: Vec<_> = path.vertices_visited().collect();
+        // Start from the vertex the order wraps around to.
+        self.initial = order[order.len() - 1..].iter().chain(order[..order.len() - 1].iter()).copied().collect();
+        self.events.clear();
+    }
+
+    fn twist(&mut self, a: (usize, usize), b: (usize, usize)) {
+        self.events.push(Event::Twist(a, b));
+    }
+
+    fn penalty(&mut self, edge: (usize, usize), penalty: u32) {
+        self.events.push(Event::Penalty(edge, penalty));
+    }
+
+    fn step(&mut self) {
+        self.events.push(Event::Step);
+    }
+
+    fn best(&mut self) {
+        self.events.push(Event::Best);
+    }
+
+    fn restore(&mut self) {
+        self.events.push(Event::Restore);
+    }
+}
+
+const MAGIC: &[u8; 4] = b"LKTR";
+
+fn write_u32<T: Write>(writer: &mut T, value: usize) -> Result<(), String> {
+    let value = u32::try_from(value).map_err(|_| format!("{} doesn't fit in 32 bits", value))?;
+    writer.write_all(&value.to_le_bytes()).map_err(|e| e.to_string())
+}
+
+fn read_u32<T: Read>(reader: &mut T) -> Result<usize, String> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(u32::from_le_bytes(bytes) as usize)
+}
+
+impl Trace {
+    /// Replays the trace calling `frame` with the path and the penalized edges after each event.
+    /// Events that don't apply to the path are reported with their position in the trace.
+    pub fn replay(&self, mut frame: impl FnMut(&Path, &BTreeMap<(usize, usize), u32>, &Event)) -> Result<Path, String> {
+        let mut path = Path::try_from_order(&self.initial).map_err(|e| format!("initial route: {}", e))?;
+        let mut penalties = BTreeMap::new();
+        let size = path.size();
+
+        // The best path is only copied once the search moves away from it.
+        let mut best = None;
+        let mut at_best = false;
+
+        for (i, event) in self.events.iter().enumerate() {
+            match *event {
+                Event::Twist(a, b) => {
+                    if at_best {
+                        best = Some(path.clone());
+                        at_best = false;
+                    }
+                    path.try_twist(a, b).map_err(|e| format!("event {}: {}", i, e))?
+                }
+                Event::Penalty((v0, v1), _) if v0 >= size || v1 >= size || v0 == v1 =>
+                    return Err(format!("event {}: ({}, {}) is not an edge", i, v0, v1)),
+                Event::Penalty((v0, v1), 0) => { penalties.remove(&(v0.min(v1), v0.max(v1))); }
+                Event::Penalty((v0, v1), penalty) => { penalties.insert((v0.min(v1), v0.max(v1)), penalty); }
+                Event::Step => {}
+                Event::Best => at_best = true,
+                Event::Restore if at_best => {}
+                Event::Restore => match best.take() {
+                    Some(previous) => {
+                        path = previous;
+                        at_best = true;
+                    }
+                    None => return Err(format!("event {}: no best route to restore", i)),
+                },
+            }
+            frame(&path, &penalties, event);
+        }
+
+        Ok(path)
+    }
+
+    /// Compact encoding: the initial order followed by a tag and the vertices of each event, all little endian.
+    pub fn write_binary<T: Write>(&self, mut writer: T) -> Result<(), String> {
+        writer.write_all(MAGIC).map_err(|e| e.to_string())?;
+        write_u32(&mut writer, self.initial.len())?;
+        for &v in self.initial.iter() {
+            write_u32(&mut writer, v)?;
+        }
+
+        write_u32(&mut writer, self.events.len())?;
+        for event in self.events.iter() {
+            let (tag, values) = match *event {
+                Event::Twist((a0, a1), (b0, b1)) => (0u8, vec![a0, a1, b0, b1]),
+                Event::Penalty((v0, v1), penalty) => (1, vec![v0, v1, penalty as usize]),
+                Event::Step => (2, vec![]),
+                Event::Best => (3, vec![]),
+                Event::Restore => (4, vec![]),
+            };
+
+            writer.write_all(&[tag]).map_err(|e| e.to_string())?;
+            for value in values {
+                write_u32(&mut writer, value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn read_binary<T: Read>(mut reader: T) -> Result<Self, String> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic).map_err(|e| e.to_string())?;
+        if &magic != MAGIC {
+            return Err("not a trace".to_owned());
+        }
+
+        let size = read_u32(&mut reader)?;
+        let initial = (0..size).map(|_| read_u32(&mut reader)).collect::<Result<_, _>>()?;
+
+        let count = read_u32(&mut reader)?;
+        let mut events = Vec::with_capacity(count.min(1 << 20));
+        for i in 0..count {
+            let err = |e: String| format!("event {}: {}", i, e);
+
+            let mut tag = [0u8];
+            reader.read_exact(&mut tag).map_err(|e| err(e.to_string()))?;
+            let mut next = || read_u32(&mut reader).map_err(err);
+
+            events.push(match tag[0] {
+                0 => Event::Twist((next()?, next()?), (next()?, next()?)),
+                1 => Event::Penalty((next()?, next()?), next()? as u32),
+                2 => Event::Step,
+                3 => Event::Best,
+                4 => Event::Restore,
+                t => return Err(err(format!("unknown tag {}", t))),
+            });
+        }
+
+        Ok(Self { initial, events })
+    }
+
+    /// One JSON document per line: the initial order followed by one line per event.
+    #[cfg(feature = "json")]
+    pub fn write_jsonl<T: Write>(&self, mut writer: T) -> Result<(), String> {
+        serde_json::to_writer(&mut writer, &self.initial).map_err(|e| e.to_string())?;
+        writeln!(writer).map_err(|e| e.to_string())?;
+
+        for event in self.events.iter() {
+            serde_json::to_writer(&mut writer, event).map_err(|e| e.to_string())?;
+            writeln!(writer).map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    pub fn read_jsonl<T: std::io::BufRead>(reader: T) -> Result<Self, String> {
+        let mut lines = reader.lines().enumerate()
+            .map(|(n, l)| (n + 1, l))
+            .filter(|(_, l)| !matches!(l, Ok(l) if l.trim().is_empty()));
+
+        let (n, first) = lines.next().ok_or("missing initial route")?;
+        let initial = first.map_err(|e| e.to_string())
+            .and_then(|l| serde_json::from_str(&l).map_err(|e| e.to_string()))
+            .map_err(|e| format!("line {}: {}", n, e))?;
+
+        let events = lines
+            .map(|(n, l)| l.map_err(|e| e.to_string())
+                .and_then(|l| serde_json::from_str(&l).map_err(|e| e.to_string()))
+                .map_err(|e| format!("line {}: {}", n, e)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { initial, events })
+    }
+}
+
+/// Animation showing the route at the start, after each step of the search and at the end.
+/// The penalized edges are highlighted as they change. Each frame lasts `seconds_per_frame`.
+pub fn animated_svg(coords: &[(i32, i32)], trace: &Trace, options: &RenderOptions, seconds_per_frame: f64) -> Result<String, String> {
+    let initial = Path::try_from_order(&trace.initial).map_err(|e| format!("initial route: {}", e))?;
+    if initial.size() != coords.len() {
+        return Err(format!("trace has {} vertices, expected {}", initial.size(), coords.len()));
+    }
+
+    check(coords, &initial, options)?;
+    let frame = Frame::new(coords, options)?;
+    let mut options = options.clone();
+
+    let draw = |path: &Path, options: &RenderOptions| {
+        let mut res = String::new();
+        svg_edges(&mut res, &frame, coords, path, options);
+        res
+    };
+
+    let mut frames = vec![draw(&initial, &options)];
+    let last = trace.replay(|path, penalties, event| {
+        if *event == Event::Step {
+            options.penalized = penalties.keys().copied().collect();
+            frames.push(draw(path, &options));
+        }
+    })?;
+    frames.push(draw(&last, &options));
+
+    let mut res = String::new();
+    svg_header(&mut res, &frame);
+
+    for (i, edges) in frames.iter().enumerate() {
+        // The last frame stays once the animation is over.
+        let timing = if i + 1 < frames.len() { format!(r#"dur="{}s""#, seconds_per_frame) } else { r#"fill="freeze""#.to_owned() };
+        writeln!(res, r#"<g visibility="hidden"><set attributeName="visibility" to="visible" begin="{}s" {}/>"#,
+                 i as f64 * seconds_per_frame, timing).unwrap();
+        res.push_str(edges);
+        res.push_str("</g>\n");
+    }
+
+    svg_nodes(&mut res, &frame, coords, &options);
+    res.push_str("</svg>\n");
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::GlsConfig;
+    use crate::fixture::scattered;
+    use crate::gls_observed;
+    use crate::matrix::SymmetricMatrix;
+    use crate::render::RenderOptions;
+    use crate::trace::{animated_svg, Event, Trace};
+
+    const COORDS: [(i32, i32); 6] = [(0, 0), (10, 0), (20, 0), (20, 10), (10, 10), (0, 10)];
+
+    fn traced() -> (SymmetricMatrix, Trace, Vec<usize>) {
+        let tsp = SymmetricMatrix::from_euc_2d(&COORDS);
+        let mut trace = Trace::default();
+        let route = gls_observed(&tsp, 3, &GlsConfig::default(), &mut trace);
+        (tsp, trace, route.path.vertices_visited().collect())
+    }
+
+    #[test]
+    fn replay() {
+        let (_, trace, order) = traced();
+        assert_eq!(trace.events.iter().filter(|&&e| e == Event::Step).count(), 3);
+        assert!(trace.events.iter().any(|e| matches!(e, Event::Penalty(..))));
+
+        let path = trace.replay(|_, _, _| {}).unwrap();
+        assert_eq!(path.vertices_visited().collect::<Vec<_>>(), order);
+
+        let mut broken = trace.clone();
+        broken.events.insert(0, Event::Twist((0, 3), (1, 4)));
+        assert_eq!(broken.replay(|_, _, _| {}).err().unwrap(), "event 0: (0, 3) is not an edge of the path");
+    }
+
+    #[test]
+    fn restore() {
+        // The search ends on a worse route than the best one it found, so it goes back.
+        let tsp = scattered::<u32>(15);
+        let mut trace = Trace::default();
+        let route = gls_observed(&tsp, 50, &GlsConfig::default(), &mut trace);
+        assert!(trace.events.contains(&Event::Restore));
+
+        let path = trace.replay(|_, _, _| {}).unwrap();
+        assert_eq!(path.vertices_visited().collect::<Vec<_>>(), route.path.vertices_visited().collect::<Vec<_>>());
+
+        let mut bytes = vec![];
+        trace.write_binary(&mut bytes).unwrap();
+        assert_eq!(Trace::read_binary(&bytes[..]).unwrap(), trace);
+
+        let broken = Trace { events: vec![Event::Restore], ..trace };
+        assert_eq!(broken.replay(|_, _, _| {}).err().unwrap(), "event 0: no best route to restore");
+    }
+
+    #[test]
+    fn binary() {
+        let (_, trace, _) = traced();
+
+        let mut bytes = vec![];
+        trace.write_binary(&mut bytes).unwrap();
+        assert_eq!(Trace::read_binary(&bytes[..]).unwrap(), trace);
+
+        assert_eq!(Trace::read_binary(&b"LKTX"[..]).err().unwrap(), "not a trace");
+        assert!(Trace::read_binary(&bytes[..bytes.len() - 1]).err().unwrap().starts_with(&format!("event {}:", trace.events.len() - 1)));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn jsonl() {
+        let (_, trace, _) = traced();
+
+        let mut text = vec![];
+        trace.write_jsonl(&mut text).unwrap();
+        assert_eq!(Trace::read_jsonl(&text[..]).unwrap(), trace);
+
+        let text = "[0, 1, 2]\n{\"twist\":[[0,1],[2,0]]}\n\"step\"\n{\"penalty\":[[0,1]]}\n";
+        assert!(Trace::read_jsonl(text.as_bytes()).err().unwrap().starts_with("line 4:"));
+    }
+
+    #[test]
+    fn animation() {
+        let (_, trace, _) = traced();
+        let svg = animated_svg(&COORDS, &trace, &RenderOptions::default(), 0.5).unwrap();
+
+        // Start, one frame per step and the end.
+        assert_eq!(svg.matches("<set ").count(), 5);
+        assert_eq!(svg.matches(r#"fill="freeze""#).count(), 1);
+        assert_eq!(svg.matches("<circle").count(), COORDS.len());
+    }
+}
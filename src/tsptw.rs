@@ -0,0 +1,336 @@
+use std::fmt::{Display, Formatter};
+use std::fmt;
+use crate::matrix::SymmetricMatrix;
+use crate::path::Path;
+use crate::route::Route;
+
+/// Time window of a vertex. Service must start in `ready..=due` and takes `service` units of time.
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub struct TimeWindow {
+    pub ready: u32,
+    pub due: u32,
+    pub service: u32,
+}
+
+impl TimeWindow {
+    pub fn new(ready: u32, due: u32, service: u32) -> Self {
+        Self::try_new(ready, due, service).unwrap()
+    }
+
+    /// Same as `new`, but reports a window that closes before it opens instead of panicking.
+    pub fn try_new(ready: u32, due: u32, service: u32) -> Result<Self, String> {
+        if ready > due {
+            return Err(format!("ready time {} after due time {}", ready, due));
+        }
+        Ok(Self { ready, due, service })
+    }
+
+    /// Window that never constrains the vertex.
+    pub fn unbounded() -> Self {
+        Self::new(0, u32::MAX, 0)
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub struct Schedule {
+    pub travel: u32,
+    pub lateness: u32,
+    /// Whether the schedule goes through the path in the opposite direction of `Path::vertices_visited`.
+    pub reversed: bool,
+}
+
+impl Schedule {
+    pub fn is_feasible(&self) -> bool {
+        self.lateness == 0
+    }
+
+    /// Objective minimized by the local search. Lateness is weighted by `penalty`.
+    /// Computed in 64 bits so it never overflows.
+    pub fn objective(&self, penalty: u32) -> u64 {
+        self.travel as u64 + penalty as u64 * self.lateness as u64
+    }
+}
+
+/// Route with the time it spends past the due dates of its schedule.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct TsptwRoute {
+    pub route: Route,
+    pub lateness: u32,
+}
+
+impl TsptwRoute {
+    pub fn is_feasible(&self) -> bool {
+        self.lateness == 0
+    }
+}
+
+impl Display for TsptwRoute {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "TsptwRoute {{ cost: {}, lateness: {}, {} }}", self.route.cost, self.lateness, self.route.path)
+    }
+}
+
+/// Schedule up to some vertex of the route: the time service starts there and the lateness so far.
+#[derive(Copy, Clone)]
+struct Partial {
+    time: u32,
+    vertex: usize,
+    lateness: u32,
+}
+
+/// Time windows for every vertex of the problem. The vertex 0 is the depot where the schedule starts and ends.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct TimeWindows(Vec<TimeWindow>);
+
+impl TimeWindows {
+    pub fn new(windows: Vec<TimeWindow>) -> Self {
+        assert!(windows.len() > 1);
+        Self(windows)
+    }
+
+    /// Reads one window per line, as the ready time, the due time and the service time of the vertex.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut windows = vec![];
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() { continue; }
+            let err = |msg: String| format!("line {}: {}", line_number + 1, msg);
+
+            let values = line.split_whitespace()
+                .map(|t| t.parse::<u32>().map_err(|e| err(e.to_string())))
+                .collect::<Result<Vec<_>, _>>()?;
+            match values[..] {
+                [ready, due, service] => windows.push(TimeWindow::try_new(ready, due, service).map_err(err)?),
+                _ => return Err(err(format!("expected 3 values, found {}", values.len()))),
+            }
+        }
+
+        if windows.len() < 2 {
+            return Err(format!("expected at least 2 windows, found {}", windows.len()));
+        }
+        Ok(Self::new(windows))
+    }
+
+    pub fn size(&self) -> usize {
+        self.0.len()
+    }
+
+    fn start(&self) -> Partial {
+        Partial { time: self.0[0].ready, vertex: 0, lateness: 0 }
+    }
+
+    /// Goes on to the next vertex. Times and lateness saturate instead of overflowing.
+    #[inline]
+    fn visit(&self, tsp: &SymmetricMatrix, partial: Partial, next: usize) -> Partial {
+        let window = self.0[next];
+        let arrival = partial.time
+            .saturating_add(self.0[partial.vertex].service)
+            .saturating_add(tsp[(partial.vertex, next)]);
+        let start = arrival.max(window.ready);
+
+        Partial { time: start, vertex: next, lateness: partial.lateness.saturating_add(start.saturating_sub(window.due)) }
+    }
+
+    /// Lateness of visiting the vertices in the given order, starting and ending at the depot.
+    fn lateness<I: Iterator<Item=usize>>(&self, tsp: &SymmetricMatrix, order: I) -> u32 {
+        order.fold(self.start(), |partial, next| self.visit(tsp, partial, next)).lateness
+    }
+
+    /// Schedule of the path in its best direction.
+    pub fn schedule(&self, tsp: &SymmetricMatrix, path: &Path, buffer: &mut Vec<usize>) -> Schedule {
+        debug_assert_eq!(tsp.size(), self.size());
+
+        buffer.clear();
+        buffer.extend(path.vertices_visited());
+
+        let travel = tsp.cost(path);
+        let forward = self.lateness(tsp, buffer.iter().copied());
+
+        // The reversed order must still end in the depot.
+        let backward = buffer.iter().rev().skip(1).copied().chain(Some(0));
+        let backward = self.lateness(tsp, backward);
+
+        if backward < forward {
+            Schedule { travel, lateness: backward, reversed: true }
+        } else {
+            Schedule { travel, lateness: forward, reversed: false }
+        }
+    }
+
+    /// Route visiting the vertices sorted by their due time.
+    pub fn earliest_due(&self, tsp: &SymmetricMatrix) -> TsptwRoute {
+        let mut order: Vec<_> = (0..self.size()).collect();
+        order[1..].sort_by_key(|&v| (self.0[v].due, self.0[v].ready));
+
+        let path = Path::from_order(&order);
+        let schedule = self.schedule(tsp, &path, &mut Vec::with_capacity(self.size()));
+        TsptwRoute { route: Route::new(schedule.travel, path), lateness: schedule.lateness }
+    }
+}
+
+/// Edges removed by a twist, and the lateness of the route after it.
+type Move = ((usize, usize), (usize, usize), u32);
+
+/// First twist that improves the penalized objective.
+/// The route visits `order` and goes back to the depot. The schedules of both directions are kept for every prefix,
+/// so a move is evaluated from the first vertex it changes and dropped as soon as it can't improve.
+fn improving_move(tsp: &SymmetricMatrix, windows: &TimeWindows, candidate: &TsptwRoute, penalty: u32, order: &[usize]) -> Option<Move> {
+    let size = order.len();
+    let objective = Schedule { travel: candidate.route.cost, lateness: candidate.lateness, reversed: false }.objective(penalty);
+
+    // Schedules after each vertex, going forward from the depot in `order[0]` and backward from the end.
+    let mut forward = Vec::with_capacity(size);
+    forward.push(windows.start());
+    for k in 1..size {
+        forward.push(windows.visit(tsp, forward[k - 1], order[k]));
+    }
+    let mut backward = vec![windows.start(); size + 1];
+    for k in (1..size).rev() {
+        backward[k] = windows.visit(tsp, backward[k + 1], order[k]);
+    }
+
+    // Lateness of the rest of the walk, or nothing once the objective can't go under the limit.
+    let walk = |mut partial: Partial, rest: &mut dyn Iterator<Item=usize>, limit: u64| {
+        for next in rest.chain(Some(0)) {
+            partial = windows.visit(tsp, partial, next);
+            if penalty as u64 * partial.lateness as u64 >= limit { return None; }
+        }
+        Some(partial.lateness)
+    };
+
+    for i in 0..size {
+        for j in i + 2..size {
+            // Both edges touch the depot.
+            if i == 0 && j == size - 1 { continue; }
+
+            let (a0, a1, b0, b1) = (order[i], order[i + 1], order[j], order[(j + 1) % size]);
+            let travel = candidate.route.cost as u64 + tsp[(a0, b0)] as u64 + tsp[(a1, b1)] as u64 - tsp[(a0, a1)] as u64 - tsp[(b0, b1)] as u64;
+            if travel >= objective { continue; }
+            let limit = objective - travel;
+
+            // Forward the segment from a1 to b0 is reversed, backward it's walked in order.
+            let ahead = walk(forward[i], &mut (i + 1..=j).rev().chain(j + 1..size).map(|k| order[k]), limit);
+            let behind = walk(backward[j + 1], &mut (i + 1..=j).chain((1..=i).rev()).map(|k| order[k]), limit);
+
+            if let Some(lateness) = ahead.into_iter().chain(behind).min() {
+                return Some(((a0, a1), (b0, b1), lateness));
+            }
+        }
+    }
+
+    None
+}
+
+/// 2-opt local search on the penalized objective `travel + penalty * lateness`.
+/// Evaluating a move takes O(n) at worst since the lateness of the whole schedule may change.
+pub fn local_search(tsp: &SymmetricMatrix, windows: &TimeWindows, candidate: &mut TsptwRoute, penalty: u32, order: &mut Vec<usize>) {
+    let schedule = windows.schedule(tsp, &candidate.route.path, order);
+    candidate.route.cost = schedule.travel;
+    candidate.lateness = schedule.lateness;
+
+    while let Some((a, b, lateness)) = {
+        order.clear();
+        order.extend(candidate.route.path.edges_visited().map(|(v0, _)| v0));
+        improving_move(tsp, windows, candidate, penalty, order)
+    } {
+        candidate.route.twist(tsp, a, b);
+        candidate.lateness = lateness;
+    }
+}
+
+/// Solves the TSP with time windows starting from the earliest due route.
+pub fn tsptw(tsp: &SymmetricMatrix, windows: &TimeWindows, penalty: u32) -> TsptwRoute {
+    let mut route = windows.earliest_due(tsp);
+    let mut order = Vec::with_capacity(tsp.size());
+
+    local_search(tsp, windows, &mut route, penalty, &mut order);
+
+    debug_assert!(route.route.path.is_hamiltonian());
+    route
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::SymmetricMatrix;
+    use crate::tsptw::{Schedule, TimeWindow, TimeWindows, tsptw};
+
+    fn matrix() -> SymmetricMatrix {
+        let mut matrix = SymmetricMatrix::from_size(5);
+        let data = [
+            [0, 1, 2, 5, 3],
+            [1, 0, 7, 4, 8],
+            [2, 7, 0, 1, 3],
+            [5, 4, 1, 0, 5],
+            [3, 8, 3, 5, 0],
+        ];
+        for (i, row) in data.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                matrix.set((i, j), value);
+            }
+        }
+        matrix
+    }
+
+    #[test]
+    fn unbounded_is_plain_tsp() {
+        let matrix = matrix();
+        let windows = TimeWindows::new(vec![TimeWindow::unbounded(); 5]);
+
+        let route = tsptw(&matrix, &windows, 100);
+        assert_eq!(route.route.cost, 12);
+        assert!(route.is_feasible());
+    }
+
+    #[test]
+    fn windows_force_order() {
+        let matrix = matrix();
+
+        // Vertex 2 must be visited first.
+        let windows = TimeWindows::new(vec![
+            TimeWindow::unbounded(),
+            TimeWindow::unbounded(),
+            TimeWindow::new(0, 2, 0),
+            TimeWindow::unbounded(),
+            TimeWindow::unbounded(),
+        ]);
+
+        let route = tsptw(&matrix, &windows, 100);
+        let mut buffer = vec![];
+        let schedule = windows.schedule(&matrix, &route.route.path, &mut buffer);
+
+        assert!(route.is_feasible());
+        assert_eq!(schedule.lateness, 0);
+        assert_eq!(route.route.cost, schedule.travel);
+        assert_eq!(route.route.cost, 15);
+    }
+
+    #[test]
+    fn large_penalty() {
+        let schedule = Schedule { travel: u32::MAX, lateness: u32::MAX, reversed: false };
+        assert_eq!(schedule.objective(u32::MAX), u32::MAX as u64 * (u32::MAX as u64 + 1));
+
+        // Every vertex is late, by far.
+        let matrix = matrix();
+        let mut windows = vec![TimeWindow::new(0, 0, u32::MAX / 2); 5];
+        windows[0] = TimeWindow::unbounded();
+        let windows = TimeWindows::new(windows);
+
+        let route = tsptw(&matrix, &windows, u32::MAX);
+        let schedule = windows.schedule(&matrix, &route.route.path, &mut vec![]);
+        assert_eq!((route.route.cost, route.lateness), (schedule.travel, schedule.lateness));
+        assert!(!route.is_feasible());
+    }
+
+    #[test]
+    fn parse() {
+        let windows = TimeWindows::parse("0 100 0\n\n 5 10 2\n0 4294967295 1\n").unwrap();
+        assert_eq!(windows, TimeWindows::new(vec![TimeWindow::new(0, 100, 0), TimeWindow::new(5, 10, 2), TimeWindow::new(0, u32::MAX, 1)]));
+
+        assert_eq!(TimeWindow::try_new(10, 5, 0).err().unwrap(), "ready time 10 after due time 5");
+        assert_eq!(TimeWindows::parse("0 100 0\n10 5 0").err().unwrap(), "line 2: ready time 10 after due time 5");
+        assert_eq!(TimeWindows::parse("0 100 0\n1 2").err().unwrap(), "line 2: expected 3 values, found 2");
+        assert_eq!(TimeWindows::parse("0 100 0\n1 2 -3").err().unwrap(), "line 2: invalid digit found in string");
+        assert_eq!(TimeWindows::parse("0 100 0").err().unwrap(), "expected at least 2 windows, found 1");
+    }
+}
@@ -0,0 +1,255 @@
+use std::fmt::{Display, Formatter};
+use std::fmt;
+use std::fs;
+use crate::matrix::SymmetricMatrix;
+use crate::path::{Path, PathError, HamiltonianResult};
+use crate::sop::{Precedences, PrecedenceResult, orient};
+
+/// Result of checking that a tour visits every vertex exactly once.
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum TourResult {
+    Ok,
+    WrongSize { expected: usize, found: usize },
+    OutOfRange(usize),
+    Repeated(usize),
+    NoEdgeBack(usize, usize, (usize, usize)),
+    NotVisited(usize),
+}
+
+/// Constraints the tour must respect besides being Hamiltonian.
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct Constraints {
+    pub fixed_edges: Vec<(usize, usize)>,
+    pub precedences: Option<Precedences>,
+}
+
+/// Outcome of validating a tour. Checks after the Hamiltonian one only run if it succeeds.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct Report {
+    pub tour: TourResult,
+    pub cost: Option<u32>,
+    pub missing_fixed_edges: Vec<(usize, usize)>,
+    pub precedences: Option<PrecedenceResult>,
+}
+
+impl Constraints {
+    /// Checks that the fixed edges refer only to vertices of a problem of the given size.
+    pub fn check(&self, size: usize) -> Result<(), PathError> {
+        check_edges(size, &self.fixed_edges)
+    }
+}
+
+impl Report {
+    pub fn is_valid(&self) -> bool {
+        self.tour == TourResult::Ok
+            && self.missing_fixed_edges.is_empty()
+            && !matches!(self.precedences, Some(PrecedenceResult::Violated(..)))
+    }
+
+    /// Whether the tour is valid and has the claimed cost.
+    pub fn certifies(&self, cost: u32) -> bool {
+        self.is_valid() && self.cost == Some(cost)
+    }
+
+    fn invalid(tour: TourResult) -> Self {
+        Self { tour, cost: None, missing_fixed_edges: vec![], precedences: None }
+    }
+}
+
+/// Hamiltonian check in O(n) for a tour given as a sequence of vertices.
+fn check_order(size: usize, order: &[usize]) -> TourResult {
+    if order.len() != size {
+        return TourResult::WrongSize { expected: size, found: order.len() };
+    }
+
+    let mut visited = vec![false; size];
+    for &v in order {
+        if v >= size { return TourResult::OutOfRange(v); }
+        if visited[v] { return TourResult::Repeated(v); }
+        visited[v] = true;
+    }
+
+    TourResult::Ok
+}
+
+/// Hamiltonian check in O(n) for a path.
+fn check_path(size: usize, path: &Path) -> TourResult {
+    if path.size() != size {
+        return TourResult::WrongSize { expected: size, found: path.size() };
+    }
+
+    match path.check_hamiltonian_fast() {
+        HamiltonianResult::Ok => TourResult::Ok,
+        HamiltonianResult::NoEdgeBack(v, n, adj) => TourResult::NoEdgeBack(v, n, adj),
+        HamiltonianResult::NotVisited(v) => TourResult::NotVisited(v),
+    }
+}
+
+/// Checks that edges from an external source, like fixed edges or candidate lists, are in range.
+pub fn check_edges(size: usize, edges: &[(usize, usize)]) -> Result<(), PathError> {
+    match edges.iter().find(|&&(v0, v1)| v0 >= size || v1 >= size) {
+        Some(&(v0, v1)) => Err(PathError::OutOfRange(v0.max(v1))),
+        None => Ok(()),
+    }
+}
+
+fn check_constraints(tsp: &SymmetricMatrix, path: &Path, order: &[usize], constraints: &Constraints) -> Report {
+    let size = tsp.size();
+
+    let missing_fixed_edges = constraints.fixed_edges.iter().copied()
+        .filter(|&(a, b)| a >= size || b >= size || (path[a].0 != b && path[a].1 != b))
+        .collect();
+
+    let precedences = constraints.precedences.as_ref().map(|p| {
+        // Tours start at the depot.
+        let start = order.iter().position(|&v| v == 0).unwrap();
+        let order: Vec<_> = order[start..].iter().chain(order[..start].iter()).copied().collect();
+        p.check(&order)
+    });
+
+    Report { tour: TourResult::Ok, cost: Some(tsp.cost(path)), missing_fixed_edges, precedences }
+}
+
+/// Validates a tour given as a sequence of vertices, as read by `parse_tour`.
+pub fn validate_tour(tsp: &SymmetricMatrix, tour: &[usize], constraints: &Constraints) -> Report {
+    match check_order(tsp.size(), tour) {
+        TourResult::Ok => check_constraints(tsp, &Path::from_order(tour), tour, constraints),
+        result => Report::invalid(result),
+    }
+}
+
+/// Validates a path. Precedences are checked in the direction of `Path::vertices_visited`.
+pub fn validate_path(tsp: &SymmetricMatrix, path: &Path, constraints: &Constraints) -> Report {
+    match check_path(tsp.size(), path) {
+        TourResult::Ok => {
+            check_constraints(tsp, path, &orient(path), constraints)
+        }
+        result => Report::invalid(result),
+    }
+}
+
+/// Parses the `TOUR_SECTION` of a TSPLIB tour file. Vertices are returned starting from 0.
+pub fn parse_tour(text: &str) -> Result<Vec<usize>, String> {
+    let mut lines = text.lines().enumerate().map(|(n, l)| (n + 1, l.trim()));
+
+    lines.by_ref()
+        .find(|(_, l)| l.starts_with("TOUR_SECTION"))
+        .ok_or("missing TOUR_SECTION")?;
+
+    let mut tour = vec![];
+
+    for (line_number, line) in lines {
+        if line == "EOF" { break; }
+
+        for token in line.split_whitespace() {
+            match token.parse::<i64>() {
+                Ok(-1) => return Ok(tour),
+                Ok(v) if v > 0 => tour.push(v as usize - 1),
+                Ok(v) => return Err(format!("line {}: invalid vertex {}", line_number, v)),
+                Err(e) => return Err(format!("line {}: {}", line_number, e)),
+            }
+        }
+    }
+
+    Ok(tour)
+}
+
+pub fn tour_from_file(file: &str) -> Result<Vec<usize>, String> {
+    let text = fs::read_to_string(file).map_err(|e| e.to_string())?;
+    parse_tour(&text)
+}
+
+impl Display for TourResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            TourResult::Ok => write!(f, "ok"),
+            TourResult::WrongSize { expected, found } => write!(f, "expected {} vertices, found {}", expected, found),
+            TourResult::OutOfRange(v) => write!(f, "vertex {} is out of range", v),
+            TourResult::Repeated(v) => write!(f, "vertex {} is visited more than once", v),
+            TourResult::NoEdgeBack(v, n, adj) => write!(f, "vertex {} links to {}, which links to {:?}", v, n, adj),
+            TourResult::NotVisited(v) => write!(f, "vertex {} is not visited", v),
+        }
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tour: {}", self.tour)?;
+
+        if let Some(cost) = self.cost {
+            writeln!(f, "cost: {}", cost)?;
+        }
+        for (a, b) in self.missing_fixed_edges.iter() {
+            writeln!(f, "missing fixed edge: ({}, {})", a, b)?;
+        }
+        if let Some(PrecedenceResult::Violated(a, b)) = self.precedences {
+            writeln!(f, "precedence violated: {} must be visited before {}", a, b)?;
+        }
+
+        write!(f, "valid: {}", self.is_valid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::SymmetricMatrix;
+    use crate::path::{Path, PathError};
+    use crate::sop::{Precedences, PrecedenceResult};
+    use crate::validate::{parse_tour, validate_tour, validate_path, Constraints, TourResult};
+
+    fn matrix() -> SymmetricMatrix {
+        SymmetricMatrix::from_euc_2d(&[(0, 0), (10, 0), (10, 10), (0, 10), (5, 5)])
+    }
+
+    #[test]
+    fn parse() {
+        let text = "NAME : t.tour\nTYPE : TOUR\nDIMENSION : 5\nTOUR_SECTION\n1\n2 3\n5\n4\n-1\nEOF\n";
+        assert_eq!(parse_tour(text), Ok(vec![0, 1, 2, 4, 3]));
+        assert_eq!(parse_tour("TOUR_SECTION\n1\n0\n"), Err("line 3: invalid vertex 0".to_owned()));
+    }
+
+    #[test]
+    fn tour() {
+        let tsp = matrix();
+        let none = Constraints::default();
+
+        let report = validate_tour(&tsp, &[0, 1, 4, 2, 3], &none);
+        assert!(report.certifies(44));
+
+        assert_eq!(validate_tour(&tsp, &[0, 1, 2, 3], &none).tour, TourResult::WrongSize { expected: 5, found: 4 });
+        assert_eq!(validate_tour(&tsp, &[0, 1, 2, 3, 7], &none).tour, TourResult::OutOfRange(7));
+        assert_eq!(validate_tour(&tsp, &[0, 1, 2, 1, 3], &none).tour, TourResult::Repeated(1));
+    }
+
+    #[test]
+    fn path() {
+        let tsp = matrix();
+        let none = Constraints::default();
+
+        let broken = Path::new(vec![(1, 2), (0, 2), (0, 1), (4, 4), (0, 0)]);
+        assert_eq!(validate_path(&tsp, &broken, &none).tour, TourResult::NoEdgeBack(3, 4, (0, 0)));
+
+        let disconnected = Path::new(vec![(1, 2), (0, 2), (0, 1), (4, 4), (3, 3)]);
+        assert_eq!(validate_path(&tsp, &disconnected, &none).tour, TourResult::NotVisited(3));
+
+        let path = Path::from_order(&[0, 1, 2, 4, 3]);
+        assert_eq!(validate_path(&tsp, &path, &none).cost, Some(tsp.cost(&path)));
+    }
+
+    #[test]
+    fn constraints() {
+        let tsp = matrix();
+        let constraints = Constraints {
+            fixed_edges: vec![(0, 1), (2, 4), (0, 2)],
+            precedences: Some(Precedences::new(5, &[(4, 3), (3, 1)])),
+        };
+
+        assert_eq!(constraints.check(5), Ok(()));
+        assert_eq!(constraints.check(4), Err(PathError::OutOfRange(4)));
+
+        let report = validate_tour(&tsp, &[1, 2, 4, 3, 0], &constraints);
+        assert_eq!(report.missing_fixed_edges, vec![(0, 2)]);
+        assert_eq!(report.precedences, Some(PrecedenceResult::Violated(3, 1)));
+        assert!(!report.is_valid());
+    }
+}
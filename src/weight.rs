@@ -0,0 +1,111 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, Mul, Sub};
+
+/// Numeric type of the edge weights and tour costs.
+pub trait Weight: Copy + PartialOrd + Default + Debug + Display + Send + Sync
+    + Add<Output=Self> + Sub<Output=Self> + Mul<Output=Self> + Sum {
+    const ZERO: Self;
+    const MAX: Self;
+    /// Gains up to this are rounding noise. Zero for integer weights.
+    const EPSILON: Self;
+
+    /// Converts a distance, truncating it for integer weights.
+    fn from_f64(value: f64) -> Self;
+
+    fn to_f64(self) -> f64;
+
+    /// Exact representation, used to save weights.
+    fn to_bits(self) -> u64;
+
+    fn from_bits(bits: u64) -> Self;
+
+    /// Whether replacing edges weighing `removed` with ones weighing `added` improves the tour.
+    #[inline]
+    fn improves(removed: Self, added: Self) -> bool {
+        removed > added + Self::EPSILON
+    }
+
+    /// Equality up to the rounding accumulated on costs of this magnitude.
+    fn approx_eq(self, other: Self) -> bool {
+        let (a, b) = (self.to_f64(), other.to_f64());
+        (a - b).abs() <= Self::EPSILON.to_f64() * a.abs().max(b.abs()).max(1.0)
+    }
+}
+
+macro_rules! impl_weight {
+    ($($t:ty: $epsilon:expr, $to_bits:expr, $from_bits:expr),*) => {$(
+        impl Weight for $t {
+            const ZERO: Self = 0 as $t;
+            const MAX: Self = <$t>::MAX;
+            const EPSILON: Self = $epsilon;
+
+            #[inline]
+            fn from_f64(value: f64) -> Self { value as $t }
+
+            #[inline]
+            fn to_f64(self) -> f64 { self as f64 }
+
+            fn to_bits(self) -> u64 { $to_bits(self) }
+
+            fn from_bits(bits: u64) -> Self { $from_bits(bits) }
+        }
+    )*};
+}
+
+impl_weight!(
+    u32: 0, |w| w as u64, |b| b as u32,
+    u64: 0, |w| w, |b| b,
+    f64: 1e-9, f64::to_bits, f64::from_bits
+);
+
+/// Weights that don't fit the weight type.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum WeightError {
+    /// A single edge is too heavy.
+    Edge { edge: (usize, usize), weight: f64 },
+    /// A tour may cost more than the weight type holds.
+    Tour { size: usize, max_weight: f64 },
+}
+
+/// Checks that a tour over `size` edges no heavier than `max_weight` can't overflow.
+pub fn check_tour_bound<W: Weight>(size: usize, max_weight: f64) -> Result<(), WeightError> {
+    if max_weight * size as f64 > W::MAX.to_f64() {
+        Err(WeightError::Tour { size, max_weight })
+    } else {
+        Ok(())
+    }
+}
+
+impl Display for WeightError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            WeightError::Edge { edge, weight } => write!(f, "weight {} of edge {:?} overflows", weight, edge),
+            WeightError::Tour { size, max_weight } =>
+                write!(f, "a tour of {} edges with weights up to {} may overflow", size, max_weight),
+        }
+    }
+}
+
+impl Error for WeightError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::weight::Weight;
+
+    #[test]
+    fn epsilon() {
+        assert!(u32::improves(3, 2));
+        assert!(!u32::improves(2, 2));
+
+        assert!(f64::improves(2.5, 2.0));
+        assert!(!f64::improves(0.1 + 0.2, 0.3));
+
+        assert!((0.1 + 0.2).approx_eq(0.3));
+        assert!(1e12.approx_eq(1e12 + 1e-3));
+        assert!(!2.0.approx_eq(2.001));
+        assert!(!10u32.approx_eq(11));
+    }
+}